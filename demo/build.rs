@@ -18,7 +18,7 @@ fn main() {
     // working directories.
     let path = std::path::PathBuf::from("src").canonicalize().unwrap();
     let mut b = autocxx_build::build("src/main.rs", &[&path]).unwrap();
-    b.flag_if_supported("-std=c++14").compile("autocxx-demo");
+    b.compile("autocxx-demo");
 
     println!("cargo:rerun-if-changed=src/main.rs");
     println!("cargo:rerun-if-changed=src/input.h");
@@ -36,6 +36,9 @@ pub enum BuilderError {
     NoIncludeCxxMacrosFound,
     /// Unable to create one of the directories to which we need to write
     UnableToCreateDirectory(std::io::Error, PathBuf),
+    /// Two `include_cpp!` macros asked for different C++ standards, but
+    /// their C++ is compiled together.
+    ConflictingCppStandards(String, String),
 }
 
 impl Display for BuilderError {
@@ -46,6 +49,7 @@ impl Display for BuilderError {
             BuilderError::FileWriteFail(ee, pb) => write!(f, "Unable to write to {}: {}", pb.to_string_lossy(), ee)?,
             BuilderError::NoIncludeCxxMacrosFound => write!(f, "No include_cpp! macro found")?,
             BuilderError::UnableToCreateDirectory(ee, pb) => write!(f, "Unable to create directory {}: {}", pb.to_string_lossy(), ee)?,
+            BuilderError::ConflictingCppStandards(first, second) => write!(f, "The include_cpp! macros in this file need both {} and {}, but their C++ is built together. Use the same cpp_standard! in each.", first, second)?,
         }
         Ok(())
     }
@@ -72,7 +76,29 @@ where
     I: IntoIterator<Item = T>,
     T: AsRef<OsStr>,
 {
-    build_to_custom_directory(rs_file, autocxx_incs, None, dependency_recorder)
+    build_with_clang_args(rs_file, autocxx_incs, &[], dependency_recorder)
+}
+
+/// Like [build], but any extra clang arguments (for instance `-std=c++17`)
+/// are passed both to bindgen and to the returned cc::Build.
+pub fn build_with_clang_args<P1, I, T>(
+    rs_file: P1,
+    autocxx_incs: I,
+    extra_clang_args: &[&str],
+    dependency_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+) -> BuilderResult
+where
+    P1: AsRef<Path>,
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    build_to_custom_directory(
+        rs_file,
+        autocxx_incs,
+        extra_clang_args,
+        None,
+        dependency_recorder,
+    )
 }
 
 /// Builds successfully, or exits the process displaying a suitable
@@ -87,10 +113,27 @@ where
     I: IntoIterator<Item = T>,
     T: AsRef<OsStr>,
 {
-    build(rs_file, autocxx_incs, dependency_recorder).unwrap_or_else(|err| {
-        let _ = writeln!(io::stderr(), "\n\nautocxx error: {}\n\n", report(err));
-        process::exit(1);
-    })
+    expect_build_with_clang_args(rs_file, autocxx_incs, &[], dependency_recorder)
+}
+
+/// Like [expect_build], but passes some extra arguments to clang.
+/// See [build_with_clang_args].
+pub fn expect_build_with_clang_args<P1, I, T>(
+    rs_file: P1,
+    autocxx_incs: I,
+    extra_clang_args: &[&str],
+    dependency_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+) -> BuilderSuccess
+where
+    P1: AsRef<Path>,
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    build_with_clang_args(rs_file, autocxx_incs, extra_clang_args, dependency_recorder)
+        .unwrap_or_else(|err| {
+            let _ = writeln!(io::stderr(), "\n\nautocxx error: {}\n\n", report(err));
+            process::exit(1);
+        })
 }
 
 fn report(err: BuilderError) -> String {
@@ -102,6 +145,7 @@ fn report(err: BuilderError) -> String {
 pub(crate) fn build_to_custom_directory<P1, I, T>(
     rs_file: P1,
     autocxx_incs: I,
+    extra_clang_args: &[&str],
     custom_gendir: Option<PathBuf>,
     dependency_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
 ) -> BuilderResult
@@ -132,9 +176,10 @@ where
     // pass on th
     gen_location_strategy.set_cargo_env_vars_for_build();
 
+    let extra_clang_args: Vec<String> = extra_clang_args.iter().map(|s| s.to_string()).collect();
     let mut parsed_file = crate::parse_file(rs_file).map_err(BuilderError::ParseError)?;
     parsed_file
-        .resolve_all(autocxx_inc, dependency_recorder)
+        .resolve_all_with_clang_args(autocxx_inc, &extra_clang_args, dependency_recorder)
         .map_err(BuilderError::ParseError)?;
     build_with_existing_parsed_file(parsed_file, cxxdir, incdir, rsdir)
 }
@@ -149,10 +194,33 @@ pub(crate) fn build_with_existing_parsed_file(
     let mut builder = cc::Build::new();
     builder.cpp(true);
     let mut generated_rs = Vec::new();
+    let mut cpp_flags: Vec<&String> = Vec::new();
     for include_cpp in parsed_file.get_autocxxes() {
         for inc_dir in include_cpp.include_dirs() {
             builder.include(inc_dir);
         }
+        // Compile with the same flags that bindgen saw. We can only
+        // compile with one C++ standard, so each block must agree on it.
+        for flag in include_cpp.cpp_flags() {
+            if let Some(other) = cpp_flags
+                .iter()
+                .find(|other| other.starts_with("-std=") && flag.starts_with("-std="))
+            {
+                if *other != flag {
+                    return Err(BuilderError::ConflictingCppStandards(
+                        other.to_string(),
+                        flag.clone(),
+                    ));
+                }
+            }
+            if !cpp_flags.contains(&flag) {
+                // Not flag_if_supported: if the compiler can't honour
+                // what bindgen saw, it's better to fail now than to
+                // quietly disagree about the layout of types.
+                builder.flag(flag);
+                cpp_flags.push(flag);
+            }
+        }
         let generated_code = include_cpp
             .generate_h_and_cxx()
             .map_err(BuilderError::InvalidCxx)?;
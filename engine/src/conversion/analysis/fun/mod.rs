@@ -19,17 +19,21 @@ mod rust_name_tracker;
 use crate::known_types::KNOWN_TYPES;
 use std::collections::{HashMap, HashSet};
 
-use autocxx_parser::{TypeConfig, UnsafePolicy};
+use autocxx_parser::{OverloadNaming, TypeConfig, UnsafePolicy};
+use quote::{quote, ToTokens};
 use syn::{
-    parse_quote, punctuated::Punctuated, FnArg, ForeignItemFn, Ident, LitStr, Pat, ReturnType,
-    Type, TypePtr, Visibility,
+    parse_quote, punctuated::Punctuated, FnArg, ForeignItemFn, GenericArgument, Ident, LitStr, Pat,
+    PathArguments, ReturnType, Type, TypePath, TypePtr, TypeReference, Visibility,
 };
 
 use crate::{
     conversion::{
         api::{Api, ApiAnalysis, ApiDetail, FuncToConvert, TypeKind, UnanalyzedApi, Use},
         codegen_cpp::{
-            function_wrapper::{ArgumentConversion, FunctionWrapper, FunctionWrapperPayload},
+            function_wrapper::{
+                ArgumentConversion, FunctionWrapper, FunctionWrapperPayload, HAS_VALUE_PARAM,
+            },
+            type_to_cpp::type_to_cpp,
             AdditionalNeed,
         },
         parse::type_converter::TypeConverter,
@@ -52,10 +56,12 @@ pub(crate) struct FnAnalysisBody {
     pub(crate) params: Punctuated<FnArg, syn::Token![,]>,
     pub(crate) self_ty: Option<TypeName>,
     pub(crate) ret_type: ReturnType,
+    pub(crate) ret_conversion: Option<ArgumentConversion>,
     pub(crate) is_constructor: bool,
     pub(crate) param_details: Vec<ArgumentAnalysis>,
     pub(crate) cpp_call_name: String,
     pub(crate) wrapper_function_needed: bool,
+    pub(crate) rust_wrapper_needed: bool,
     pub(crate) requires_unsafe: bool,
     pub(crate) vis: Visibility,
     pub(crate) id_for_allowlist: Option<Ident>,
@@ -97,6 +103,10 @@ pub(crate) struct FnAnalyzer<'a> {
     type_config: &'a TypeConfig,
     incomplete_types: HashSet<TypeName>,
     overload_trackers_by_mod: HashMap<Namespace, OverloadTracker>,
+    overload_naming: OverloadNaming,
+    overloaded_functions: HashSet<String>,
+    callbacks: HashMap<String, TypeName>,
+    tuples: HashMap<String, TypeName>,
 }
 
 struct FnAnalysisResult(FnAnalysisBody, Ident, HashSet<TypeName>);
@@ -105,6 +115,7 @@ impl<'a> FnAnalyzer<'a> {
     pub(crate) fn analyze_functions(
         apis: Vec<Api<PodAnalysis>>,
         unsafe_policy: UnsafePolicy,
+        overload_naming: OverloadNaming,
         type_converter: &'a mut TypeConverter,
         type_database: &'a TypeConfig,
     ) -> Result<Vec<Api<FnAnalysis>>, ConvertError> {
@@ -117,13 +128,19 @@ impl<'a> FnAnalyzer<'a> {
             type_config: type_database,
             incomplete_types: Self::build_incomplete_type_set(&apis),
             overload_trackers_by_mod: HashMap::new(),
+            overload_naming,
+            overloaded_functions: Self::find_overloaded_functions(&apis),
             pod_safe_types: Self::build_pod_safe_type_set(&apis),
+            callbacks: HashMap::new(),
+            tuples: HashMap::new(),
         };
         let mut results = Vec::new();
         for api in apis {
             let r = me.analyze_fn_api(api);
             match r {
-                Err(e) if e.is_ignorable() => eprintln!("Skipped function because: {}", e),
+                Err(e) if e.is_ignorable() || me.type_config.is_generate_all() => {
+                    eprintln!("Skipped function because: {}", e)
+                }
                 Err(e) => return Err(e),
                 Ok(Some(api)) => results.push(api),
                 Ok(None) => {}
@@ -141,6 +158,7 @@ impl<'a> FnAnalyzer<'a> {
                     for_extern_c_ts: _,
                     is_forward_declaration: true,
                     bindgen_mod_item: _,
+                    bitfield_accessors: _,
                     analysis: _,
                 } => Some(api.typename()),
                 _ => None,
@@ -156,6 +174,11 @@ impl<'a> FnAnalyzer<'a> {
                     for_extern_c_ts: _,
                     is_forward_declaration: _,
                     bindgen_mod_item: _,
+                    bitfield_accessors: _,
+                    analysis: TypeKind::Pod,
+                }
+                | ApiDetail::ExternCppType {
+                    rust_path: _,
                     analysis: TypeKind::Pod,
                 } => Some(api.typename()),
                 _ => None,
@@ -188,7 +211,14 @@ impl<'a> FnAnalyzer<'a> {
                 ty_details,
                 additional_cpp,
             },
-            _ => panic!("Function analysis created an extra API which wasn't a concrete type"),
+            ApiDetail::PrimitiveWrapper { ty_details, inner } => {
+                ApiDetail::PrimitiveWrapper { ty_details, inner }
+            }
+            ApiDetail::Callback { params, ret_type } => ApiDetail::Callback { params, ret_type },
+            ApiDetail::Tuple { members } => ApiDetail::Tuple { members },
+            _ => panic!(
+                "Function analysis created an extra API which wasn't a concrete type, primitive wrapper, callback or tuple"
+            ),
         };
         Api {
             ns: api.ns,
@@ -213,7 +243,12 @@ impl<'a> FnAnalyzer<'a> {
                 ty_details,
                 additional_cpp,
             },
+            ApiDetail::PrimitiveWrapper { ty_details, inner } => {
+                ApiDetail::PrimitiveWrapper { ty_details, inner }
+            }
             ApiDetail::StringConstructor => ApiDetail::StringConstructor,
+            ApiDetail::Callback { params, ret_type } => ApiDetail::Callback { params, ret_type },
+            ApiDetail::Tuple { members } => ApiDetail::Tuple { members },
             ApiDetail::Function { fun, analysis: _ } => {
                 let analysis = self.analyze_foreign_fn(&api.ns, &fun)?;
                 match analysis {
@@ -228,18 +263,28 @@ impl<'a> FnAnalyzer<'a> {
             ApiDetail::Const { const_item } => ApiDetail::Const { const_item },
             ApiDetail::Typedef { payload } => ApiDetail::Typedef { payload },
             ApiDetail::CType { typename } => ApiDetail::CType { typename },
+            ApiDetail::BitfieldUnit { items } => ApiDetail::BitfieldUnit { items },
+            ApiDetail::ExternCppType {
+                rust_path,
+                analysis,
+            } => ApiDetail::ExternCppType {
+                rust_path,
+                analysis,
+            },
             // Just changes to this one...
             ApiDetail::Type {
                 ty_details,
                 for_extern_c_ts,
                 is_forward_declaration,
                 bindgen_mod_item,
+                bitfield_accessors,
                 analysis,
             } => ApiDetail::Type {
                 ty_details,
                 for_extern_c_ts,
                 is_forward_declaration,
                 bindgen_mod_item,
+                bitfield_accessors,
                 analysis,
             },
             ApiDetail::OpaqueTypedef => ApiDetail::OpaqueTypedef,
@@ -284,7 +329,95 @@ impl<'a> FnAnalyzer<'a> {
     }
 
     fn is_on_allowlist(&self, type_name: &TypeName) -> bool {
-        self.type_config.is_on_allowlist(&type_name.to_cpp_name())
+        type_name.is_on_allowlist(self.type_config)
+    }
+
+    /// The fully-qualified C++ name of a function or method, as used
+    /// to identify it in `rename!` directives.
+    fn qualified_cpp_name(ns: &Namespace, self_ty: Option<&TypeName>, cpp_name: &str) -> String {
+        match self_ty {
+            Some(self_ty) => format!("{}::{}", self_ty.to_cpp_name(), cpp_name),
+            None => TypeName::new(ns, cpp_name).to_cpp_name(),
+        }
+    }
+
+    /// The C++ types of the parameters, excluding any `this`. Returns
+    /// `None` if any of them can't be expressed in C++.
+    fn get_cpp_param_types(
+        params: &Punctuated<FnArg, syn::Token![,]>,
+        param_details: &[ArgumentAnalysis],
+    ) -> Option<Vec<String>> {
+        params
+            .iter()
+            .zip(param_details.iter())
+            .filter(|(_, pd)| pd.self_type.is_none())
+            .map(|(param, _)| match param {
+                FnArg::Typed(pt) => type_to_cpp(&pt.ty).ok(),
+                FnArg::Receiver(_) => None,
+            })
+            .collect()
+    }
+
+    /// Finds any name chosen by a `rename!` directive for this function
+    /// or method. A rename which gives parameter types, and so picks out
+    /// a single overload, takes precedence over one which gives the name alone.
+    fn get_rename(&self, qualified_name: &str, param_types: Option<&[String]>) -> Option<String> {
+        let with_params =
+            param_types.map(|types| format!("{}({})", qualified_name, types.join(", ")));
+        with_params
+            .and_then(|name| self.type_config.get_rename(&name))
+            .or_else(|| self.type_config.get_rename(qualified_name))
+            .map(str::to_string)
+    }
+
+    /// Finds all the functions and methods which have more than one
+    /// overload, identified by their qualified C++ names. We need to
+    /// know this up-front in order to give _all_ the overloads
+    /// descriptive names, rather than all but the first.
+    fn find_overloaded_functions(apis: &[Api<PodAnalysis>]) -> HashSet<String> {
+        let mut seen = HashSet::new();
+        let mut overloaded = HashSet::new();
+        for api in apis {
+            if let ApiDetail::Function { fun, analysis: _ } = &api.detail {
+                let cpp_name = Self::get_bindgen_original_name_annotation(&fun.item)
+                    .unwrap_or_else(|| fun.item.sig.ident.to_string());
+                let self_ty = Self::find_self_type(fun);
+                let qualified_name = Self::qualified_cpp_name(&api.ns, self_ty.as_ref(), &cpp_name);
+                if !seen.insert(qualified_name.clone()) {
+                    overloaded.insert(qualified_name);
+                }
+            }
+        }
+        overloaded
+    }
+
+    /// Works out the type to which a method belongs, prior to full
+    /// analysis of its parameters.
+    fn find_self_type(fun: &FuncToConvert) -> Option<TypeName> {
+        fun.item
+            .sig
+            .inputs
+            .iter()
+            .find_map(|arg| match arg {
+                FnArg::Typed(pt) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                    (Pat::Ident(pp), Type::Ptr(TypePtr { elem, .. })) if pp.ident == "this" => {
+                        match elem.as_ref() {
+                            Type::Path(typ) => {
+                                let this_type = TypeName::from_type_path(typ);
+                                if this_type.is_cvoid() {
+                                    fun.virtual_this_type.clone()
+                                } else {
+                                    Some(this_type)
+                                }
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .or_else(|| fun.self_ty.clone())
     }
 
     fn avoid_generating_type(&self, type_name: &TypeName) -> bool {
@@ -356,7 +489,6 @@ impl<'a> FnAnalyzer<'a> {
             .next()
             .cloned();
         let virtual_this_encountered = param_details.iter().any(|pd| pd.virtual_this_encountered);
-        let requires_unsafe = param_details.iter().any(|pd| pd.requires_unsafe);
 
         let is_static_method = if self_ty.is_none() {
             // Even if we can't find a 'self' parameter this could conceivably
@@ -386,6 +518,23 @@ impl<'a> FnAnalyzer<'a> {
         } else {
             cpp_call_name.clone()
         };
+        let qualified_name = Self::qualified_cpp_name(ns, self_ty.as_ref(), &cpp_call_name);
+        let cpp_param_types = Self::get_cpp_param_types(&params, &param_details);
+        // If asked, name overloads after their parameter types, so that
+        // the names don't depend on the order of declarations.
+        let ideal_rust_name = match &cpp_param_types {
+            Some(cpp_param_types)
+                if self.overload_naming == OverloadNaming::Descriptive
+                    && self.overloaded_functions.contains(&qualified_name) =>
+            {
+                OverloadTracker::get_descriptive_name(&ideal_rust_name, cpp_param_types)
+            }
+            _ => ideal_rust_name,
+        };
+        // The user may have chosen a name using rename!
+        let rename = self.get_rename(&qualified_name, cpp_param_types.as_deref());
+        self.apply_slice_params(&qualified_name, ns, fun, &mut params, &mut param_details)?;
+        let requires_unsafe = param_details.iter().any(|pd| pd.requires_unsafe);
         if let Some(self_ty) = &self_ty {
             if !self.is_on_allowlist(&self_ty) {
                 // Bindgen will output methods for types which have been encountered
@@ -394,6 +543,15 @@ impl<'a> FnAnalyzer<'a> {
                 // It may, for instance, be a private type.
                 return Ok(None);
             }
+            if self
+                .type_config
+                .get_extern_cpp_type(&self_ty.to_cpp_name())
+                .is_some()
+            {
+                // Methods belong with the bindings from which we're borrowing
+                // this type, not with ours.
+                return Ok(None);
+            }
             // Method or static method.
             let type_ident = self_ty.get_final_ident().to_string();
             // bindgen generates methods with the name:
@@ -403,8 +561,16 @@ impl<'a> FnAnalyzer<'a> {
             // We want to feed cxx methods with just the method name, so let's
             // strip off the class name.
             let overload_tracker = self.overload_trackers_by_mod.entry(ns.clone()).or_default();
-            rust_name = overload_tracker.get_method_real_name(&type_ident, ideal_rust_name);
-            if rust_name.starts_with(&type_ident) {
+            let is_constructor_name = ideal_rust_name.starts_with(&type_ident);
+            rust_name = match &rename {
+                // Renamed items are tracked by their new name, so that they
+                // don't disturb the numbering of any other overloads.
+                Some(renamed) => {
+                    overload_tracker.get_method_real_name(&type_ident, renamed.clone())
+                }
+                None => overload_tracker.get_method_real_name(&type_ident, ideal_rust_name),
+            };
+            if is_constructor_name {
                 // It's a constructor. bindgen generates
                 // fn new(this: *Type, ...args)
                 // We want
@@ -413,8 +579,10 @@ impl<'a> FnAnalyzer<'a> {
                 // fn make_unique(...args) -> UniquePtr<Type>
                 // If there are multiple constructors, bindgen generates
                 // new, new1, new2 etc. and we'll keep those suffixes.
-                let constructor_suffix = &rust_name[type_ident.len()..];
-                rust_name = format!("make_unique{}", constructor_suffix);
+                if rename.is_none() {
+                    let constructor_suffix = &rust_name[type_ident.len()..];
+                    rust_name = format!("make_unique{}", constructor_suffix);
+                }
                 // Strip off the 'this' arg.
                 params = params.into_iter().skip(1).collect();
                 param_details.remove(0);
@@ -424,7 +592,7 @@ impl<'a> FnAnalyzer<'a> {
             // Not a method.
             // What shall we call this function? It may be overloaded.
             let overload_tracker = self.overload_trackers_by_mod.entry(ns.clone()).or_default();
-            rust_name = overload_tracker.get_function_real_name(ideal_rust_name);
+            rust_name = overload_tracker.get_function_real_name(rename.unwrap_or(ideal_rust_name));
         }
 
         // The name we use within the cxx::bridge mod may be different
@@ -478,6 +646,12 @@ impl<'a> FnAnalyzer<'a> {
         let ret_type_conversion_needed = ret_type_conversion
             .as_ref()
             .map_or(false, |x| x.work_needed());
+        // Some conversions need work on the Rust side of the bridge too.
+        let rust_wrapper_needed = param_details
+            .iter()
+            .map(|pd| &pd.conversion)
+            .chain(ret_type_conversion.iter())
+            .any(|conv| conv.rust_work_needed());
         let differently_named_method = self_ty.is_some() && (cxxbridge_name != rust_name);
         let wrapper_function_needed = param_conversion_needed
             || ret_type_conversion_needed
@@ -517,7 +691,7 @@ impl<'a> FnAnalyzer<'a> {
                 is_a_method: is_a_method && !is_constructor && !is_static_method,
             })));
             // Now modify the cxx::bridge entry we're going to make.
-            if let Some(conversion) = ret_type_conversion {
+            if let Some(conversion) = &ret_type_conversion {
                 let new_ret_type = conversion.unconverted_rust_type();
                 ret_type = parse_quote!(
                     -> #new_ret_type
@@ -537,12 +711,24 @@ impl<'a> FnAnalyzer<'a> {
                     #arg_name: #type_name
                 ));
             }
+            if ret_type_conversion
+                .iter()
+                .any(|x| x.needs_has_value_param())
+            {
+                let has_value = make_ident(HAS_VALUE_PARAM);
+                params.push(parse_quote!(
+                    #has_value: &mut bool
+                ));
+            }
         }
 
         // Bits copied from below
         let mut use_alias_required = None;
         let mut rename_using_rust_attr = false;
-        if cxxbridge_name == rust_name {
+        if rust_wrapper_needed {
+            // The Rust wrapper function is called by the right name
+            // already, so no renaming is required.
+        } else if cxxbridge_name == rust_name {
             if !is_a_method {
                 // Mark that this name is now occupied in the output
                 // namespace of cxx, so that future functions we encounter
@@ -576,6 +762,8 @@ impl<'a> FnAnalyzer<'a> {
                 Use::Unused,
                 self_ty.clone().map(|ty| make_ident(ty.get_final_ident())),
             )
+        } else if rust_wrapper_needed {
+            (make_ident(&rust_name), Use::UsedFromBindgen, None)
         } else {
             match use_alias_required {
                 None => (make_ident(&rust_name), Use::Used, None),
@@ -592,10 +780,12 @@ impl<'a> FnAnalyzer<'a> {
                 params,
                 self_ty,
                 ret_type,
+                ret_conversion: ret_type_conversion,
                 is_constructor,
                 param_details,
                 cpp_call_name,
                 wrapper_function_needed,
+                rust_wrapper_needed,
                 requires_unsafe,
                 vis,
                 id_for_allowlist,
@@ -666,10 +856,42 @@ impl<'a> FnAnalyzer<'a> {
                     }
                     _ => old_pat,
                 };
-                let (new_ty, deps, requires_unsafe) =
+                let is_string_view = Self::is_string_view(&pt.ty);
+                let (new_ty, mut deps, requires_unsafe) =
                     self.convert_boxed_type(pt.ty, ns, treat_as_reference)?;
-                let was_reference = matches!(new_ty.as_ref(), Type::Reference(_));
-                let conversion = self.argument_conversion_details(&new_ty);
+                let callback = self.analyze_callback(new_ty.as_ref(), ns)?;
+                let tuple = match (&callback, &self_type) {
+                    (None, None) => self.analyze_pod_tuple(new_ty.as_ref(), &deps, true),
+                    _ => None,
+                };
+                let span = match (&callback, &self_type) {
+                    (None, None) => self.analyze_span(new_ty.as_ref())?,
+                    _ => None,
+                };
+                let was_reference = callback.is_none()
+                    && tuple.is_none()
+                    && matches!(new_ty.as_ref(), Type::Reference(_));
+                let is_array = matches!(new_ty.as_ref(),
+                    Type::Reference(typr) if matches!(typr.elem.as_ref(), Type::Array(_)));
+                let conversion = if let Some((conversion, callback_deps)) = callback {
+                    deps.extend(callback_deps);
+                    conversion
+                } else if let Some((tuple, cpp_template, members, tuple_deps)) = tuple {
+                    deps = tuple_deps;
+                    ArgumentConversion::new_to_tuple(tuple, cpp_template, members)
+                } else if let Some(elem) = span {
+                    ArgumentConversion::new_to_span(elem)
+                } else if Self::contains_param_only_type(&deps) {
+                    return Err(ConvertError::UnsupportedType(
+                        new_ty.to_token_stream().to_string(),
+                    ));
+                } else if is_string_view {
+                    ArgumentConversion::new_to_string_view(new_ty.as_ref().clone())
+                } else if is_array && self_type.is_none() {
+                    ArgumentConversion::new_from_array(new_ty.as_ref().clone(), treat_as_reference)
+                } else {
+                    self.argument_conversion_details(&new_ty)?
+                };
                 pt.pat = Box::new(new_pat.clone());
                 pt.ty = new_ty;
                 (
@@ -689,28 +911,359 @@ impl<'a> FnAnalyzer<'a> {
         })
     }
 
-    fn conversion_details<F>(&self, ty: &Type, conversion_direction: F) -> ArgumentConversion
+    fn conversion_details<F, G>(
+        &self,
+        ty: &Type,
+        conversion_direction: F,
+        optional_conversion_direction: G,
+    ) -> Result<ArgumentConversion, ConvertError>
     where
         F: FnOnce(Type) -> ArgumentConversion,
+        G: FnOnce(Type, bool) -> ArgumentConversion,
     {
         match ty {
+            Type::Path(p) if KNOWN_TYPES.is_optional(&TypeName::from_type_path(p)) => {
+                let payload = Self::get_first_generic_arg(p).ok_or_else(|| {
+                    ConvertError::UnsupportedType(ty.to_token_stream().to_string())
+                })?;
+                let by_value = matches!(payload, Type::Path(payload)
+                    if self.pod_safe_types.contains(&TypeName::from_type_path(payload)));
+                Ok(optional_conversion_direction(payload.clone(), by_value))
+            }
             Type::Path(p) => {
                 if self.pod_safe_types.contains(&TypeName::from_type_path(p)) {
-                    ArgumentConversion::new_unconverted(ty.clone())
+                    Ok(ArgumentConversion::new_unconverted(ty.clone()))
                 } else {
-                    conversion_direction(ty.clone())
+                    Ok(conversion_direction(ty.clone()))
+                }
+            }
+            _ => Ok(ArgumentConversion::new_unconverted(ty.clone())),
+        }
+    }
+
+    /// If this is a std::function (or a const reference to one), works
+    /// out how to create it from a Rust closure. Each distinct signature
+    /// gets its own Rust type to hold such closures, declared in an
+    /// extern "Rust" block so that C++ can call them.
+    fn analyze_callback(
+        &mut self,
+        ty: &Type,
+        ns: &Namespace,
+    ) -> Result<Option<(ArgumentConversion, HashSet<TypeName>)>, ConvertError> {
+        let typ = match ty {
+            Type::Path(typ) => typ,
+            Type::Reference(TypeReference {
+                elem,
+                mutability: None,
+                ..
+            }) => match elem.as_ref() {
+                Type::Path(typ) => typ,
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        if !KNOWN_TYPES.is_function(&TypeName::from_type_path(typ)) {
+            return Ok(None);
+        }
+        let unsupported = || ConvertError::UnsupportedType(ty.to_token_stream().to_string());
+        // bindgen represents the function type as an Option of a function
+        // pointer.
+        let signature = match Self::get_first_generic_arg(typ).ok_or_else(unsupported)? {
+            Type::Path(typ) if KNOWN_TYPES.is_optional(&TypeName::from_type_path(typ)) => {
+                Self::get_first_generic_arg(typ).ok_or_else(unsupported)?
+            }
+            signature => signature,
+        };
+        let signature = match signature {
+            Type::BareFn(signature) if signature.variadic.is_none() => signature,
+            _ => return Err(unsupported()),
+        };
+        let mut deps = HashSet::new();
+        let mut params = Vec::new();
+        for input in &signature.inputs {
+            let (ty, these_deps, _) =
+                self.convert_boxed_type(Box::new(input.ty.clone()), ns, true)?;
+            if !self.is_acceptable_callback_type(&ty) {
+                return Err(unsupported());
+            }
+            deps.extend(these_deps);
+            params.push(*ty);
+        }
+        let ret_type = match &signature.output {
+            ReturnType::Default => ReturnType::Default,
+            ReturnType::Type(rarrow, ty) => {
+                let (ty, these_deps, _) = self.convert_boxed_type(ty.clone(), ns, true)?;
+                if !self.is_acceptable_callback_type(&ty) {
+                    return Err(unsupported());
                 }
+                deps.extend(these_deps);
+                ReturnType::Type(*rarrow, ty)
+            }
+        };
+        let signature_key = quote! { #(#params),* #ret_type }.to_string();
+        let callback = match self.callbacks.get(&signature_key) {
+            Some(callback) => callback.clone(),
+            None => {
+                let callback = TypeName::new(
+                    &Namespace::new(),
+                    &format!("AutocxxCallback{}", self.callbacks.len()),
+                );
+                self.callbacks.insert(signature_key, callback.clone());
+                self.extra_apis.push(UnanalyzedApi {
+                    ns: Namespace::new(),
+                    id: make_ident(callback.get_final_ident()),
+                    deps: deps.clone(),
+                    detail: ApiDetail::Callback {
+                        params: params.clone(),
+                        ret_type: ret_type.clone(),
+                    },
+                });
+                callback
+            }
+        };
+        let callback_path = callback.to_type_path();
+        deps.insert(callback);
+        Ok(Some((
+            // C++ may copy the std::function, call it from any thread and
+            // call it re-entrantly, so the closure must cope with all of
+            // those.
+            ArgumentConversion::new_to_function(
+                Type::Path(callback_path),
+                parse_quote! {
+                    impl Fn(#(#params),*) #ret_type + Send + Sync + 'static
+                },
+            ),
+            deps,
+        )))
+    }
+
+    /// If this is a std::pair or std::tuple (or, if `allow_reference`, a
+    /// const reference to one) of types which can be passed by value,
+    /// works out the shared struct in which it should cross the bridge.
+    /// Each distinct list of member types gets its own struct. Returns
+    /// that struct, the C++ template, the member types, and the
+    /// dependencies of a function which uses it in place of the concrete
+    /// type.
+    fn analyze_pod_tuple(
+        &mut self,
+        ty: &Type,
+        deps: &HashSet<TypeName>,
+        allow_reference: bool,
+    ) -> Option<(Type, String, Vec<Type>, HashSet<TypeName>)> {
+        let typ = match ty {
+            Type::Path(typ) => typ,
+            Type::Reference(TypeReference {
+                elem,
+                mutability: None,
+                ..
+            }) if allow_reference => match elem.as_ref() {
+                Type::Path(typ) => typ,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let concrete = TypeName::from_type_path(typ);
+        let def = self.type_converter.get_concrete_definition(&concrete)?;
+        let members = match KNOWN_TYPES.get_pair_members(def) {
+            Some((first, second)) => vec![first, second],
+            None => KNOWN_TYPES.get_tuple_members(def)?,
+        };
+        if members.is_empty() || !members.iter().all(|ty| self.is_by_value_type(ty)) {
+            return None;
+        }
+        let cpp_template = match def {
+            Type::Path(def) => KNOWN_TYPES.special_cpp_name(&TypeName::from_type_path(def))?,
+            _ => return None,
+        };
+        let members: Vec<Type> = members.into_iter().cloned().collect();
+        let mut deps = deps.clone();
+        deps.remove(&concrete);
+        let key = quote! { #(#members),* }.to_string();
+        let tuple = match self.tuples.get(&key) {
+            Some(tuple) => tuple.clone(),
+            None => {
+                let tuple = TypeName::new(
+                    &Namespace::new(),
+                    &format!("AutocxxTuple{}", self.tuples.len()),
+                );
+                self.tuples.insert(key, tuple.clone());
+                self.extra_apis.push(UnanalyzedApi {
+                    ns: Namespace::new(),
+                    id: make_ident(tuple.get_final_ident()),
+                    deps: deps.clone(),
+                    detail: ApiDetail::Tuple {
+                        members: members.clone(),
+                    },
+                });
+                tuple
+            }
+        };
+        let tuple_path = tuple.to_type_path();
+        deps.insert(tuple);
+        Some((Type::Path(tuple_path), cpp_template, members, deps))
+    }
+
+    /// Closures may take and return anything which cxx can pass by
+    /// value, or references.
+    fn is_acceptable_callback_type(&self, ty: &Type) -> bool {
+        matches!(ty, Type::Reference(_)) || self.is_by_value_type(ty)
+    }
+
+    /// Whether cxx can pass this type by value.
+    fn is_by_value_type(&self, ty: &Type) -> bool {
+        matches!(ty, Type::Path(typ)
+            if self.pod_safe_types.contains(&TypeName::from_type_path(typ)))
+    }
+
+    /// Whether a std::function or std::span is lurking somewhere other
+    /// than a parameter, where we know how to create it from a closure
+    /// or slice.
+    fn contains_param_only_type(deps: &HashSet<TypeName>) -> bool {
+        deps.iter()
+            .any(|tn| KNOWN_TYPES.is_function(tn) || KNOWN_TYPES.is_span(tn))
+    }
+
+    /// If this is a std::span (or a const reference to one), the type of
+    /// its elements, which we pass from Rust as a slice.
+    fn analyze_span(&self, ty: &Type) -> Result<Option<Type>, ConvertError> {
+        let typ = match ty {
+            Type::Path(typ) => typ,
+            Type::Reference(TypeReference {
+                elem,
+                mutability: None,
+                ..
+            }) => match elem.as_ref() {
+                Type::Path(typ) => typ,
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        if !KNOWN_TYPES.is_span(&TypeName::from_type_path(typ)) {
+            return Ok(None);
+        }
+        match Self::get_first_generic_arg(typ) {
+            Some(elem) if self.is_by_value_type(elem) => Ok(Some(elem.clone())),
+            _ => Err(ConvertError::UnsupportedType(
+                ty.to_token_stream().to_string(),
+            )),
+        }
+    }
+
+    /// Applies any `slice_param!` directives for this function, each of
+    /// which replaces a pointer parameter, and the integer length
+    /// parameter which follows it, with a single slice parameter.
+    fn apply_slice_params(
+        &mut self,
+        qualified_name: &str,
+        ns: &Namespace,
+        fun: &ForeignItemFn,
+        params: &mut Punctuated<FnArg, syn::Token![,]>,
+        param_details: &mut Vec<ArgumentAnalysis>,
+    ) -> Result<(), ConvertError> {
+        let type_config = self.type_config;
+        let mut slices: Vec<(usize, ArgumentConversion)> = Vec::new();
+        for (pointer_param, len_param) in type_config.get_slice_params(qualified_name) {
+            let problem = |problem: String| {
+                ConvertError::InvalidSliceParam(qualified_name.to_string(), problem)
+            };
+            let position = |name: &str| {
+                param_details
+                    .iter()
+                    .position(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == name))
+            };
+            let idx = position(pointer_param)
+                .ok_or_else(|| problem(format!("there's no parameter {}", pointer_param)))?;
+            if position(len_param) != Some(idx + 1) {
+                return Err(problem(format!(
+                    "{} must immediately follow {}",
+                    len_param, pointer_param
+                )));
+            }
+            if slices
+                .iter()
+                .any(|(other, _)| *other + 1 >= idx && *other <= idx + 1)
+            {
+                return Err(problem(format!(
+                    "{} overlaps another slice parameter",
+                    pointer_param
+                )));
+            }
+            let param_type = |idx: usize| match fun.sig.inputs.iter().nth(idx) {
+                Some(FnArg::Typed(pt)) => Some(pt.ty.as_ref()),
+                _ => None,
+            };
+            let ptr = match param_type(idx) {
+                Some(Type::Ptr(ptr)) => ptr,
+                _ => return Err(problem(format!("{} isn't a pointer", pointer_param))),
+            };
+            let len = match param_type(idx + 1) {
+                Some(len) if KNOWN_TYPES.is_integer(len) => len,
+                _ => return Err(problem(format!("{} isn't an integer", len_param))),
+            };
+            let (elem, _, _) = self.convert_boxed_type(ptr.elem.clone(), ns, false)?;
+            if !self.is_by_value_type(&elem) {
+                return Err(problem(format!(
+                    "{} can't be held in a slice",
+                    elem.to_token_stream()
+                )));
             }
-            _ => ArgumentConversion::new_unconverted(ty.clone()),
+            slices.push((
+                idx,
+                ArgumentConversion::new_from_slice(*elem, ptr.mutability.is_some(), len.clone()),
+            ));
+        }
+        // Work from the last parameter to the first, so that removing each
+        // length parameter doesn't move those of the slices still to come.
+        slices.sort_by_key(|(idx, _)| std::cmp::Reverse(*idx));
+        for (idx, conversion) in slices {
+            let pd = &mut param_details[idx];
+            pd.conversion = conversion;
+            pd.requires_unsafe = false;
+            param_details.remove(idx + 1);
+            *params = params
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != idx + 1)
+                .map(|(_, param)| param.clone())
+                .collect();
+        }
+        Ok(())
+    }
+
+    fn get_first_generic_arg(typ: &TypePath) -> Option<&Type> {
+        match &typ.path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(ab) => match ab.args.first() {
+                Some(GenericArgument::Type(ty)) => Some(ty),
+                _ => None,
+            },
+            _ => None,
         }
     }
 
-    fn argument_conversion_details(&self, ty: &Type) -> ArgumentConversion {
-        self.conversion_details(ty, ArgumentConversion::new_from_unique_ptr)
+    /// Whether this type, as found in the bindgen output, is a
+    /// std::string_view. By the time we've converted it, it's
+    /// indistinguishable from a rust::Str, since both are &str.
+    fn is_string_view(ty: &Type) -> bool {
+        matches!(ty, Type::Path(typ) if KNOWN_TYPES.is_string_view(typ))
+    }
+
+    fn argument_conversion_details(&self, ty: &Type) -> Result<ArgumentConversion, ConvertError> {
+        self.conversion_details(
+            ty,
+            ArgumentConversion::new_from_unique_ptr,
+            ArgumentConversion::new_to_optional,
+        )
     }
 
-    fn return_type_conversion_details(&self, ty: &Type) -> ArgumentConversion {
-        self.conversion_details(ty, ArgumentConversion::new_to_unique_ptr)
+    fn return_type_conversion_details(
+        &self,
+        ty: &Type,
+    ) -> Result<ArgumentConversion, ConvertError> {
+        self.conversion_details(
+            ty,
+            ArgumentConversion::new_to_unique_ptr,
+            ArgumentConversion::new_from_optional,
+        )
     }
 
     fn convert_return_type(
@@ -727,11 +1280,28 @@ impl<'a> FnAnalyzer<'a> {
                 deps: HashSet::new(),
             },
             ReturnType::Type(rarrow, boxed_type) => {
+                let is_string_view = Self::is_string_view(boxed_type);
                 // TODO remove the below clone
-                let (boxed_type, deps, _) =
+                let (mut boxed_type, mut deps, _) =
                     self.convert_boxed_type(boxed_type.clone(), ns, convert_ptr_to_reference)?;
+                if Self::contains_param_only_type(&deps) {
+                    return Err(ConvertError::UnsupportedType(
+                        boxed_type.to_token_stream().to_string(),
+                    ));
+                }
                 let was_reference = matches!(boxed_type.as_ref(), Type::Reference(_));
-                let conversion = self.return_type_conversion_details(boxed_type.as_ref());
+                let tuple = self.analyze_pod_tuple(boxed_type.as_ref(), &deps, false);
+                let conversion = if is_string_view {
+                    // A std::string_view needn't contain UTF-8, so we return
+                    // its bytes rather than a &str.
+                    boxed_type = Box::new(parse_quote! { &[u8] });
+                    ArgumentConversion::new_from_string_view(boxed_type.as_ref().clone())
+                } else if let Some((tuple, cpp_template, members, tuple_deps)) = tuple {
+                    deps = tuple_deps;
+                    ArgumentConversion::new_from_tuple(tuple, cpp_template, members)
+                } else {
+                    self.return_type_conversion_details(boxed_type.as_ref())?
+                };
                 ReturnTypeAnalysis {
                     rt: ReturnType::Type(*rarrow, boxed_type),
                     conversion: Some(conversion),
@@ -804,10 +1374,18 @@ impl Api<FnAnalysis> {
                 for_extern_c_ts: _,
                 is_forward_declaration: _,
                 bindgen_mod_item: _,
+                bitfield_accessors: _,
                 analysis: _,
             } => Use::Used,
+            ApiDetail::ExternCppType { .. } => Use::Used,
             ApiDetail::Function { fun: _, analysis } => analysis.use_stmt.clone(),
             ApiDetail::Typedef { .. } => Use::UsedFromBindgen,
+            // The cxx::bridge refers to the type which holds closures,
+            // which lives alongside the bindgen types.
+            ApiDetail::Callback { .. } => Use::UsedFromBindgen,
+            // Likewise primitive wrappers, which people may need to
+            // construct in order to put them into a CxxVector.
+            ApiDetail::PrimitiveWrapper { .. } => Use::UsedFromBindgen,
             _ => Use::Unused,
         }
     }
@@ -826,7 +1404,14 @@ impl Api<FnAnalysis> {
                 ty_details: _,
                 additional_cpp,
             } => Some(additional_cpp.clone()),
+            ApiDetail::PrimitiveWrapper { inner, .. } => Some(
+                AdditionalNeed::PrimitiveWrapperTypedef(self.typename(), Box::new(inner.clone())),
+            ),
             ApiDetail::CType { typename } => Some(AdditionalNeed::CTypeTypedef(typename.clone())),
+            ApiDetail::Callback { .. } => {
+                Some(AdditionalNeed::CallbackDeclaration(self.typename()))
+            }
+            ApiDetail::Tuple { .. } => Some(AdditionalNeed::TupleDeclaration(self.typename())),
             _ => None,
         }
     }
@@ -41,6 +41,24 @@ impl OverloadTracker {
         self.get_name(Some(type_name), found_name)
     }
 
+    /// Makes a name for one of several overloads which describes its
+    /// parameter types, e.g. `draw_int` for `draw(int)`. Unlike the
+    /// numeric suffixes, such names don't depend on the order in which
+    /// the overloads are declared.
+    pub(crate) fn get_descriptive_name(found_name: &str, param_types: &[String]) -> String {
+        let mut name = found_name.to_string();
+        for param_type in param_types {
+            let param_type = param_type.strip_prefix("const ").unwrap_or(param_type);
+            let words: Vec<&str> = param_type
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .filter(|word| !word.is_empty())
+                .collect();
+            name.push('_');
+            name.push_str(&words.join("_"));
+        }
+        name
+    }
+
     fn get_name(&mut self, type_name: Option<&str>, cpp_method_name: String) -> String {
         let registry = match type_name {
             Some(type_name) => self
@@ -80,4 +98,19 @@ mod tests {
         assert_eq!(ot.get_method_real_name("Ty2", "bob".into()), "bob");
         assert_eq!(ot.get_method_real_name("Ty2", "bob".into()), "bob1");
     }
+
+    #[test]
+    fn test_descriptive_names() {
+        let name = |params: &[&str]| {
+            let params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+            OverloadTracker::get_descriptive_name("draw", &params)
+        };
+        assert_eq!(name(&[]), "draw");
+        assert_eq!(name(&["int"]), "draw_int");
+        assert_eq!(name(&["float"]), "draw_float");
+        assert_eq!(
+            name(&["unsigned long", "const std::string&"]),
+            "draw_unsigned_long_std_string"
+        );
+    }
 }
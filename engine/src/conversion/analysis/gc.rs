@@ -43,10 +43,7 @@ pub(crate) fn filter_apis_by_following_edges_from_allowlist(
 ) -> Vec<Api<FnAnalysis>> {
     let mut todos: Vec<_> = apis
         .iter()
-        .filter(|api| {
-            let tnforal = api.typename_for_allowlist();
-            type_config.is_on_allowlist(&tnforal.to_cpp_name())
-        })
+        .filter(|api| api.typename_for_allowlist().is_on_allowlist(type_config))
         .map(Api::typename)
         .collect();
     let mut by_typename: HashMap<TypeName, Vec<Api<FnAnalysis>>> = HashMap::new();
@@ -25,7 +25,7 @@ use crate::{
 };
 use autocxx_parser::TypeConfig;
 use std::collections::HashMap;
-use syn::{Item, ItemStruct, Type};
+use syn::{Field, Ident, Item, ItemStruct, ItemUnion, Type};
 
 #[derive(Clone)]
 enum PodState {
@@ -82,7 +82,12 @@ impl ByValueChecker {
         type_config: &TypeConfig,
     ) -> Result<ByValueChecker, ConvertError> {
         let mut byvalue_checker = ByValueChecker::new();
-        for blocklisted in type_config.get_blocklist() {
+        // Blocklist patterns need no entry here: types which match them
+        // are never parsed, so anything containing them is already unsafe.
+        for blocklisted in type_config
+            .get_blocklist()
+            .filter(|ty| !TypeConfig::is_pattern(ty))
+        {
             let tn = TypeName::new_from_user_input(blocklisted);
             let safety = PodState::UnsafeToBePod(format!("type {} is on the blocklist", &tn));
             byvalue_checker
@@ -117,10 +122,12 @@ impl ByValueChecker {
                     for_extern_c_ts: _,
                     is_forward_declaration: _,
                     bindgen_mod_item,
+                    bitfield_accessors: _,
                     analysis: _,
                 } => match bindgen_mod_item {
                     None => {}
                     Some(Item::Struct(s)) => byvalue_checker.ingest_struct(&s, &api.ns),
+                    Some(Item::Union(u)) => byvalue_checker.ingest_union(&u, &api.ns),
                     Some(Item::Enum(_)) => {
                         byvalue_checker
                             .results
@@ -128,15 +135,47 @@ impl ByValueChecker {
                     }
                     _ => {}
                 },
+                ApiDetail::ExternCppType { .. } => {
+                    // We can't see inside these types, so we take the user's
+                    // word for whether they're POD.
+                    let tn = api.typename();
+                    let state = if type_config.is_pod_requested(&tn.to_cpp_name()) {
+                        PodState::IsPod
+                    } else {
+                        PodState::UnsafeToBePod(format!(
+                            "type {} is an extern_cpp_type! which was not also given to generate_pod!",
+                            tn
+                        ))
+                    };
+                    byvalue_checker
+                        .results
+                        .insert(tn, StructDetails::new(state));
+                }
+                ApiDetail::BitfieldUnit { .. } => {
+                    byvalue_checker
+                        .results
+                        .insert(api.typename(), StructDetails::new(PodState::IsPod));
+                }
                 ApiDetail::OpaqueTypedef => byvalue_checker.ingest_nonpod_type(api.typename()),
                 _ => {}
             }
         }
-        let pod_requests = type_config
+        // Exact names are always requested, so that we complain if they
+        // never turn up. Patterns only apply to the types we actually found.
+        let mut pod_requests: Vec<TypeName> = type_config
             .get_pod_requests()
             .iter()
+            .filter(|ty| !TypeConfig::is_pattern(ty))
             .map(|ty| TypeName::new_from_user_input(ty))
             .collect();
+        for api in apis {
+            if let ApiDetail::Type { .. } = api.detail {
+                let tn = api.typename();
+                if type_config.is_pod_requested(&tn.to_cpp_name()) && !pod_requests.contains(&tn) {
+                    pod_requests.push(tn);
+                }
+            }
+        }
         byvalue_checker
             .satisfy_requests(pod_requests)
             .map_err(ConvertError::UnsafePodType)?;
@@ -144,10 +183,25 @@ impl ByValueChecker {
     }
 
     fn ingest_struct(&mut self, def: &ItemStruct, ns: &Namespace) {
-        // For this struct, work out whether it _could_ be safe as a POD.
-        let tyname = TypeName::new(ns, &def.ident.to_string());
+        self.ingest_fields(&def.ident, def.fields.iter(), Self::has_vtable(def), ns)
+    }
+
+    /// A union is POD if all its members could be, just like a struct.
+    fn ingest_union(&mut self, def: &ItemUnion, ns: &Namespace) {
+        self.ingest_fields(&def.ident, def.fields.named.iter(), false, ns)
+    }
+
+    fn ingest_fields<'a>(
+        &mut self,
+        id: &Ident,
+        fields: impl Iterator<Item = &'a Field>,
+        has_vtable: bool,
+        ns: &Namespace,
+    ) {
+        // For this struct or union, work out whether it _could_ be safe as a POD.
+        let tyname = TypeName::new(ns, &id.to_string());
         let mut field_safety_problem = PodState::SafeToBePod;
-        let fieldlist = Self::get_field_types(def);
+        let fieldlist = Self::get_field_types(fields);
         for ty_id in &fieldlist {
             match self.results.get(ty_id) {
                 None => {
@@ -166,7 +220,7 @@ impl ByValueChecker {
                 }
             }
         }
-        if Self::has_vtable(def) {
+        if has_vtable {
             let reason = format!(
                 "Type {} could not be POD because it has virtual functions.",
                 tyname
@@ -239,14 +293,18 @@ impl ByValueChecker {
         )
     }
 
-    fn get_field_types(def: &ItemStruct) -> Vec<TypeName> {
+    fn get_field_types<'a>(fields: impl Iterator<Item = &'a Field>) -> Vec<TypeName> {
         let mut results = Vec::new();
-        for f in &def.fields {
-            let fty = &f.ty;
+        for f in fields {
+            let mut fty = &f.ty;
+            // An array is POD if its elements are.
+            while let Type::Array(arr) = fty {
+                fty = arr.elem.as_ref();
+            }
             if let Type::Path(p) = fty {
                 results.push(TypeName::from_type_path(&p));
             }
-            // TODO handle anything else which bindgen might spit out, e.g. arrays?
+            // TODO handle anything else which bindgen might spit out?
         }
         results
     }
@@ -265,7 +323,7 @@ impl ByValueChecker {
 mod tests {
     use super::ByValueChecker;
     use crate::types::{Namespace, TypeName};
-    use syn::{parse_quote, Ident, ItemStruct};
+    use syn::{parse_quote, Ident, ItemStruct, ItemUnion};
 
     fn ty_from_ident(id: &Ident) -> TypeName {
         TypeName::new_from_user_input(&id.to_string())
@@ -330,6 +388,34 @@ mod tests {
         assert!(bvc.is_pod(&t_id));
     }
 
+    #[test]
+    fn test_with_array() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Bar {
+                a: [i32; 16usize],
+                b: [[u8; 4usize]; 2usize],
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        bvc.satisfy_requests(vec![t_id.clone()]).unwrap();
+        assert!(bvc.is_pod(&t_id));
+    }
+
+    #[test]
+    fn test_with_array_of_cxxstring() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Bar {
+                a: [CxxString; 4usize],
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        assert!(bvc.satisfy_requests(vec![t_id]).is_err());
+    }
+
     #[test]
     fn test_with_cxxstring() {
         let mut bvc = ByValueChecker::new();
@@ -343,4 +429,33 @@ mod tests {
         bvc.ingest_struct(&t, &Namespace::new());
         assert!(bvc.satisfy_requests(vec![t_id]).is_err());
     }
+
+    #[test]
+    fn test_union() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemUnion = parse_quote! {
+            union Bar {
+                a: i32,
+                b: [u8; 4usize],
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_union(&t, &Namespace::new());
+        bvc.satisfy_requests(vec![t_id.clone()]).unwrap();
+        assert!(bvc.is_pod(&t_id));
+    }
+
+    #[test]
+    fn test_union_with_cxxstring() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemUnion = parse_quote! {
+            union Bar {
+                a: i32,
+                b: CxxString,
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_union(&t, &Namespace::new());
+        assert!(bvc.satisfy_requests(vec![t_id]).is_err());
+    }
 }
@@ -18,12 +18,12 @@ use std::collections::HashSet;
 
 use autocxx_parser::TypeConfig;
 use byvalue_checker::ByValueChecker;
-use syn::{Item, ItemStruct};
+use syn::{parse_quote, Field, Item, ItemUnion, Type};
 
 use crate::{
     conversion::{
         api::{Api, ApiAnalysis, ApiDetail, TypeKind, UnanalyzedApi},
-        codegen_rs::make_non_pod,
+        codegen_rs::{make_non_pod, new_non_pod_struct},
         parse::type_converter::TypeConverter,
         ConvertError,
     },
@@ -86,34 +86,75 @@ fn analyze_pod_api(
             ty_details,
             additional_cpp,
         },
+        ApiDetail::PrimitiveWrapper { ty_details, inner } => {
+            ApiDetail::PrimitiveWrapper { ty_details, inner }
+        }
         ApiDetail::StringConstructor => ApiDetail::StringConstructor,
+        ApiDetail::Callback { params, ret_type } => ApiDetail::Callback { params, ret_type },
+        ApiDetail::Tuple { members } => ApiDetail::Tuple { members },
         ApiDetail::Function { fun, analysis } => ApiDetail::Function { fun, analysis },
         ApiDetail::Const { const_item } => ApiDetail::Const { const_item },
         ApiDetail::Typedef { payload } => ApiDetail::Typedef { payload },
         ApiDetail::CType { typename } => ApiDetail::CType { typename },
+        ApiDetail::BitfieldUnit { items } => ApiDetail::BitfieldUnit { items },
+        ApiDetail::ExternCppType {
+            rust_path,
+            analysis: _,
+        } => ApiDetail::ExternCppType {
+            rust_path,
+            analysis: if byvalue_checker.is_pod(&ty_id) {
+                TypeKind::Pod
+            } else {
+                TypeKind::NonPod
+            },
+        },
         // Just changes to this one...
         ApiDetail::Type {
             ty_details,
             for_extern_c_ts,
             is_forward_declaration,
             mut bindgen_mod_item,
+            mut bitfield_accessors,
             analysis: _,
         } => {
             let type_kind = if is_forward_declaration {
                 TypeKind::ForwardDeclaration
             } else if byvalue_checker.is_pod(&ty_id) {
                 // It's POD so let's mark dependencies on things in its field
-                if let Some(Item::Struct(ref s)) = bindgen_mod_item {
-                    get_struct_field_types(type_converter, &api.ns, &s, &mut new_deps, extra_apis)?;
-                } // otherwise might be an enum, etc.
+                match bindgen_mod_item {
+                    Some(Item::Struct(ref s)) => get_field_types(
+                        type_converter,
+                        &api.ns,
+                        s.fields.iter(),
+                        &mut new_deps,
+                        extra_apis,
+                    )?,
+                    Some(Item::Union(ref mut u)) => {
+                        get_field_types(
+                            type_converter,
+                            &api.ns,
+                            u.fields.named.iter(),
+                            &mut new_deps,
+                            extra_apis,
+                        )?;
+                        make_pod_union(u);
+                    }
+                    _ => {} // otherwise might be an enum, etc.
+                }
                 TypeKind::Pod
             } else {
                 // It's non-POD. So also, make the fields opaque...
-                if let Some(Item::Struct(ref mut s)) = bindgen_mod_item {
-                    make_non_pod(s);
-                } // otherwise might be an enum, etc.
-                  // ... and say we don't depend on other types.
+                match bindgen_mod_item {
+                    Some(Item::Struct(ref mut s)) => make_non_pod(s),
+                    Some(Item::Union(ref u)) => {
+                        bindgen_mod_item = Some(Item::Struct(new_non_pod_struct(u.ident.clone())))
+                    }
+                    _ => {} // otherwise might be an enum, etc.
+                }
+                // ... and say we don't depend on other types.
                 new_deps.clear();
+                // Opaque fields leave no bitfields to access.
+                bitfield_accessors.clear();
                 TypeKind::NonPod
             };
             ApiDetail::Type {
@@ -121,6 +162,7 @@ fn analyze_pod_api(
                 for_extern_c_ts,
                 is_forward_declaration,
                 bindgen_mod_item,
+                bitfield_accessors,
                 analysis: type_kind,
             }
         }
@@ -134,17 +176,43 @@ fn analyze_pod_api(
     })
 }
 
-fn get_struct_field_types(
+fn get_field_types<'a>(
     type_converter: &mut TypeConverter,
     ns: &Namespace,
-    s: &ItemStruct,
+    fields: impl Iterator<Item = &'a Field>,
     deps: &mut HashSet<TypeName>,
     extra_apis: &mut Vec<UnanalyzedApi>,
 ) -> Result<(), ConvertError> {
-    for f in &s.fields {
+    for f in fields {
         let annotated = type_converter.convert_type(f.ty.clone(), ns, false)?;
         extra_apis.extend(annotated.extra_apis);
         deps.extend(annotated.types_encountered);
     }
     Ok(())
 }
+
+/// Rust unions may only contain `Copy` types or `ManuallyDrop`s, and we
+/// never ask bindgen to derive `Copy`. So wrap any member which isn't
+/// obviously `Copy`.
+fn make_pod_union(u: &mut ItemUnion) {
+    for f in u.fields.named.iter_mut() {
+        if !is_obviously_copy(&f.ty) {
+            let ty = &f.ty;
+            f.ty = parse_quote! { ::std::mem::ManuallyDrop<#ty> };
+        }
+    }
+}
+
+/// Pointers, and primitives, which bindgen refers to without the `root`
+/// prefix it uses for C++ types.
+fn is_obviously_copy(ty: &Type) -> bool {
+    match ty {
+        Type::Ptr(_) => true,
+        Type::Array(arr) => is_obviously_copy(&arr.elem),
+        Type::Path(typ) => {
+            typ.path.segments.first().unwrap().ident != "root"
+                && typ.path.segments.iter().all(|s| s.arguments.is_empty())
+        }
+        _ => false,
+    }
+}
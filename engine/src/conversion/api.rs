@@ -15,7 +15,9 @@
 use crate::types::{Namespace, TypeName};
 use proc_macro2::TokenStream;
 use std::collections::HashSet;
-use syn::{ForeignItemFn, Ident, ImplItem, Item, ItemConst, ItemType, ItemUse};
+use syn::{
+    ForeignItemFn, Ident, ImplItem, Item, ItemConst, ItemType, ItemUse, ReturnType, Type, TypePath,
+};
 
 use super::{codegen_cpp::AdditionalNeed, parse::type_converter::TypeConverter};
 
@@ -90,9 +92,26 @@ pub(crate) enum ApiDetail<T: ApiAnalysis> {
         ty_details: TypeApiDetails,
         additional_cpp: AdditionalNeed,
     },
+    /// A synthetic newtype around a primitive type (e.g. `u32` or
+    /// `c_int`), which `cxx` will allow within a `UniquePtr` or
+    /// `CxxVector` where the primitive itself isn't allowed.
+    PrimitiveWrapper {
+        ty_details: TypeApiDetails,
+        inner: Type,
+    },
     /// A simple note that we want to make a constructor for
     /// a `std::string` on the heap.
     StringConstructor,
+    /// A synthetic Rust type holding a closure, from which we
+    /// create a `std::function` with this signature.
+    Callback {
+        params: Vec<Type>,
+        ret_type: ReturnType,
+    },
+    /// A synthetic shared struct in which we pass a `std::pair` or
+    /// `std::tuple` of types which can be held by value across the
+    /// bridge, and which is a Rust tuple on either side of it.
+    Tuple { members: Vec<Type> },
     /// A function. May include some analysis.
     Function {
         fun: FuncToConvert,
@@ -110,8 +129,21 @@ pub(crate) enum ApiDetail<T: ApiAnalysis> {
         for_extern_c_ts: TokenStream,
         is_forward_declaration: bool,
         bindgen_mod_item: Option<Item>,
+        /// Methods which `bindgen` generated to get and set any
+        /// bitfields within this struct.
+        bitfield_accessors: Vec<ImplItem>,
+        analysis: T::TypeAnalysis,
+    },
+    /// A type declared with `extern_cpp_type!`, for which bindings
+    /// already exist elsewhere, so we refer to those rather than
+    /// generating a new type.
+    ExternCppType {
+        rust_path: TypePath,
         analysis: T::TypeAnalysis,
     },
+    /// The generic storage type which `bindgen` uses for bitfields,
+    /// and its methods, which we pass through to our output verbatim.
+    BitfieldUnit { items: Vec<Item> },
     /// A variable-length C integer type (e.g. int, unsigned long).
     CType { typename: TypeName },
     /// A typedef which doesn't point to any actual useful kind of
@@ -12,15 +12,66 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::conversion::{type_to_cpp, ConvertError};
-use crate::{known_types::type_lacks_copy_constructor, types::Namespace};
+use crate::conversion::{codegen_cpp::type_to_cpp::array_len, type_to_cpp, ConvertError};
+use crate::{
+    known_types::type_lacks_copy_constructor,
+    types::{make_ident, Namespace},
+};
+use itertools::Itertools;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
 use syn::{parse_quote, Ident, Type};
 
+/// The name of the extra out-parameter used to report whether a
+/// returned std::optional had a value.
+pub(crate) const HAS_VALUE_PARAM: &str = "autocxx_gen_has_value";
+
+/// The name of a field within the shared struct in which a std::pair
+/// or std::tuple crosses the bridge.
+pub(crate) fn tuple_field_name(index: usize) -> Ident {
+    make_ident(format!("m{}", index))
+}
+
 #[derive(Clone)]
 enum ArgumentConversionType {
     None,
     FromUniquePtrToValue,
     FromValueToUniquePtr,
+    // cxx doesn't know about std::optional, so we convert to and from
+    // a Rust Option on both sides of the bridge. For these, the
+    // unwrapped type is the type within the optional.
+    FromUniquePtrToOptional,
+    FromSliceToOptional,
+    FromOptionalToUniquePtr,
+    FromOptionalToValue,
+    // cxx passes &str as rust::Str, which we convert to
+    // std::string_view. We return a std::string_view as a slice of
+    // bytes, since it needn't contain UTF-8.
+    FromStrToStringView,
+    FromStringViewToBytes,
+    // cxx passes a reference to a Rust array as a reference to a
+    // std::array, whereas C++ functions take a pointer to (or,
+    // occasionally, a reference to) a C array.
+    FromArrayToPointer,
+    FromArrayToArrayReference,
+    // cxx passes a Rust slice as a rust::Slice, which we split into the
+    // pointer and length which C++ functions take, or make into a
+    // std::span. For these, the unwrapped type is the type of the
+    // elements, and any payload the type of the length parameter, which
+    // the Rust wrapper checks the slice's length fits into.
+    FromSliceToPointerAndLength(Box<Type>),
+    FromMutSliceToPointerAndLength(Box<Type>),
+    FromMutSliceToSpan,
+    // A Rust closure, boxed up within a type declared in an extern
+    // "Rust" block, which we wrap in a std::function. The payload is
+    // the type of closure we accept.
+    FromBoxToFunction(Box<Type>),
+    // cxx knows nothing of std::pair or std::tuple, so one of types
+    // which can be passed by value crosses the bridge as a shared struct
+    // of ours, and is a Rust tuple outside it. The payload is the C++
+    // template and the types of the members.
+    FromStructToTuple(Box<(String, Vec<Type>)>),
+    FromTupleToStruct(Box<(String, Vec<Type>)>),
 }
 
 #[derive(Clone)]
@@ -51,38 +102,403 @@ impl ArgumentConversion {
         }
     }
 
+    /// A std::string_view parameter, which is a &str in Rust.
+    pub(crate) fn new_to_string_view(ty: Type) -> Self {
+        ArgumentConversion {
+            unwrapped_type: ty,
+            conversion: ArgumentConversionType::FromStrToStringView,
+        }
+    }
+
+    /// A std::string_view return value, which is a &[u8] in Rust.
+    pub(crate) fn new_from_string_view(ty: Type) -> Self {
+        ArgumentConversion {
+            unwrapped_type: ty,
+            conversion: ArgumentConversionType::FromStringViewToBytes,
+        }
+    }
+
+    /// A parameter which is a reference to an array in Rust, and was
+    /// either an array (and so in fact a pointer) or a reference to an
+    /// array in C++.
+    pub(crate) fn new_from_array(ty: Type, was_cpp_reference: bool) -> Self {
+        ArgumentConversion {
+            unwrapped_type: ty,
+            conversion: if was_cpp_reference {
+                ArgumentConversionType::FromArrayToArrayReference
+            } else {
+                ArgumentConversionType::FromArrayToPointer
+            },
+        }
+    }
+
+    /// A pointer parameter, and the length parameter which immediately
+    /// follows it, which together are a slice of `elem` in Rust. `len`
+    /// is the integer type of the length parameter.
+    pub(crate) fn new_from_slice(elem: Type, mutable: bool, len: Type) -> Self {
+        let len = Box::new(len);
+        ArgumentConversion {
+            unwrapped_type: elem,
+            conversion: if mutable {
+                ArgumentConversionType::FromMutSliceToPointerAndLength(len)
+            } else {
+                ArgumentConversionType::FromSliceToPointerAndLength(len)
+            },
+        }
+    }
+
+    /// A std::span parameter, which is a mutable slice of `elem` in
+    /// Rust. bindgen doesn't tell us whether the elements are const, so
+    /// we always require a mutable slice, which C++ can view either way.
+    pub(crate) fn new_to_span(elem: Type) -> Self {
+        ArgumentConversion {
+            unwrapped_type: elem,
+            conversion: ArgumentConversionType::FromMutSliceToSpan,
+        }
+    }
+
+    /// A std::function parameter. `callback` is the Rust type which
+    /// holds the closure, and `closure` the type of closure accepted.
+    pub(crate) fn new_to_function(callback: Type, closure: Type) -> Self {
+        ArgumentConversion {
+            unwrapped_type: callback,
+            conversion: ArgumentConversionType::FromBoxToFunction(Box::new(closure)),
+        }
+    }
+
+    /// A std::pair or std::tuple parameter, which is a Rust tuple.
+    /// `tuple` is the shared struct in which it crosses the bridge.
+    pub(crate) fn new_to_tuple(tuple: Type, cpp_template: String, members: Vec<Type>) -> Self {
+        ArgumentConversion {
+            unwrapped_type: tuple,
+            conversion: ArgumentConversionType::FromStructToTuple(Box::new((
+                cpp_template,
+                members,
+            ))),
+        }
+    }
+
+    /// A std::pair or std::tuple return value, which is a Rust tuple.
+    /// `tuple` is the shared struct in which it crosses the bridge.
+    pub(crate) fn new_from_tuple(tuple: Type, cpp_template: String, members: Vec<Type>) -> Self {
+        ArgumentConversion {
+            unwrapped_type: tuple,
+            conversion: ArgumentConversionType::FromTupleToStruct(Box::new((
+                cpp_template,
+                members,
+            ))),
+        }
+    }
+
+    /// A std::optional parameter. Those containing types which can be
+    /// passed by value are passed across the bridge as a slice of zero
+    /// or one items, others as a possibly-null `UniquePtr`.
+    pub(crate) fn new_to_optional(payload: Type, by_value: bool) -> Self {
+        ArgumentConversion {
+            unwrapped_type: payload,
+            conversion: if by_value {
+                ArgumentConversionType::FromSliceToOptional
+            } else {
+                ArgumentConversionType::FromUniquePtrToOptional
+            },
+        }
+    }
+
+    /// A std::optional return value. Those containing types which can be
+    /// returned by value are returned along with a flag saying whether
+    /// there was a value, others as a possibly-null `UniquePtr`.
+    pub(crate) fn new_from_optional(payload: Type, by_value: bool) -> Self {
+        ArgumentConversion {
+            unwrapped_type: payload,
+            conversion: if by_value {
+                ArgumentConversionType::FromOptionalToValue
+            } else {
+                ArgumentConversionType::FromOptionalToUniquePtr
+            },
+        }
+    }
+
     pub(crate) fn work_needed(&self) -> bool {
         !matches!(self.conversion, ArgumentConversionType::None)
     }
 
+    /// Whether this also needs converting on the Rust side of the
+    /// bridge, in which case we generate a Rust wrapper function too.
+    pub(crate) fn rust_work_needed(&self) -> bool {
+        self.is_optional() || self.is_function() || self.is_tuple() || self.has_slice_length()
+    }
+
+    /// Whether this is a returned std::optional which reports whether
+    /// it had a value using an extra out-parameter.
+    pub(crate) fn needs_has_value_param(&self) -> bool {
+        matches!(self.conversion, ArgumentConversionType::FromOptionalToValue)
+    }
+
+    pub(crate) fn is_string_view(&self) -> bool {
+        matches!(
+            self.conversion,
+            ArgumentConversionType::FromStrToStringView
+                | ArgumentConversionType::FromStringViewToBytes
+        )
+    }
+
+    pub(crate) fn is_array(&self) -> bool {
+        matches!(
+            self.conversion,
+            ArgumentConversionType::FromArrayToPointer
+                | ArgumentConversionType::FromArrayToArrayReference
+        )
+    }
+
+    pub(crate) fn is_slice(&self) -> bool {
+        matches!(
+            self.conversion,
+            ArgumentConversionType::FromSliceToPointerAndLength(_)
+                | ArgumentConversionType::FromMutSliceToPointerAndLength(_)
+                | ArgumentConversionType::FromMutSliceToSpan
+        )
+    }
+
+    pub(crate) fn is_span(&self) -> bool {
+        matches!(self.conversion, ArgumentConversionType::FromMutSliceToSpan)
+    }
+
+    /// Whether this is a slice passed to C++ along with its length, which
+    /// we must check fits in the length parameter.
+    fn has_slice_length(&self) -> bool {
+        matches!(
+            self.conversion,
+            ArgumentConversionType::FromSliceToPointerAndLength(_)
+                | ArgumentConversionType::FromMutSliceToPointerAndLength(_)
+        )
+    }
+
+    pub(crate) fn is_function(&self) -> bool {
+        matches!(
+            self.conversion,
+            ArgumentConversionType::FromBoxToFunction(_)
+        )
+    }
+
+    pub(crate) fn is_tuple(&self) -> bool {
+        matches!(
+            self.conversion,
+            ArgumentConversionType::FromStructToTuple(_)
+                | ArgumentConversionType::FromTupleToStruct(_)
+        )
+    }
+
+    pub(crate) fn is_optional(&self) -> bool {
+        matches!(
+            self.conversion,
+            ArgumentConversionType::FromUniquePtrToOptional
+                | ArgumentConversionType::FromSliceToOptional
+                | ArgumentConversionType::FromOptionalToUniquePtr
+                | ArgumentConversionType::FromOptionalToValue
+        )
+    }
+
     pub(crate) fn unconverted_type(&self) -> Result<String, ConvertError> {
         match self.conversion {
-            ArgumentConversionType::FromUniquePtrToValue => self.wrapped_type(),
+            ArgumentConversionType::FromUniquePtrToValue
+            | ArgumentConversionType::FromUniquePtrToOptional => self.wrapped_type(),
+            ArgumentConversionType::FromSliceToOptional => Ok(format!(
+                "rust::Slice<const {}>",
+                self.unwrapped_type_as_string()?
+            )),
+            ArgumentConversionType::FromStrToStringView => Ok("rust::Str".to_string()),
+            ArgumentConversionType::FromSliceToPointerAndLength(_) => Ok(format!(
+                "rust::Slice<const {}>",
+                self.unwrapped_type_as_string()?
+            )),
+            ArgumentConversionType::FromMutSliceToPointerAndLength(_)
+            | ArgumentConversionType::FromMutSliceToSpan => {
+                Ok(format!("rust::Slice<{}>", self.unwrapped_type_as_string()?))
+            }
+            ArgumentConversionType::FromBoxToFunction(_) => {
+                Ok(format!("rust::Box<{}>", self.unwrapped_type_as_string()?))
+            }
             _ => self.unwrapped_type_as_string(),
         }
     }
 
     pub(crate) fn converted_type(&self) -> Result<String, ConvertError> {
         match self.conversion {
-            ArgumentConversionType::FromValueToUniquePtr => self.wrapped_type(),
+            ArgumentConversionType::FromValueToUniquePtr
+            | ArgumentConversionType::FromOptionalToUniquePtr => self.wrapped_type(),
+            ArgumentConversionType::FromStringViewToBytes => {
+                Ok("rust::Slice<const uint8_t>".to_string())
+            }
             _ => self.unwrapped_type_as_string(),
         }
     }
 
     pub(crate) fn unconverted_rust_type(&self) -> Type {
         match self.conversion {
-            ArgumentConversionType::FromValueToUniquePtr => self.make_unique_ptr_type(),
+            ArgumentConversionType::FromValueToUniquePtr
+            | ArgumentConversionType::FromOptionalToUniquePtr => self.make_unique_ptr_type(),
             _ => self.unwrapped_type.clone(),
         }
     }
 
     pub(crate) fn converted_rust_type(&self) -> Type {
         match self.conversion {
-            ArgumentConversionType::FromUniquePtrToValue => self.make_unique_ptr_type(),
+            ArgumentConversionType::FromUniquePtrToValue
+            | ArgumentConversionType::FromUniquePtrToOptional => self.make_unique_ptr_type(),
+            ArgumentConversionType::FromSliceToOptional
+            | ArgumentConversionType::FromSliceToPointerAndLength(_) => {
+                let innerty = &self.unwrapped_type;
+                parse_quote! {
+                    &[#innerty]
+                }
+            }
+            ArgumentConversionType::FromMutSliceToPointerAndLength(_)
+            | ArgumentConversionType::FromMutSliceToSpan => {
+                let innerty = &self.unwrapped_type;
+                parse_quote! {
+                    &mut [#innerty]
+                }
+            }
+            ArgumentConversionType::FromBoxToFunction(_) => {
+                let innerty = &self.unwrapped_type;
+                parse_quote! {
+                    Box < #innerty >
+                }
+            }
             _ => self.unwrapped_type.clone(),
         }
     }
 
+    /// The type seen by users of any Rust wrapper function, which may
+    /// differ from the type passed across the bridge.
+    pub(crate) fn outer_rust_type(&self) -> Type {
+        match self.conversion {
+            ArgumentConversionType::FromUniquePtrToOptional
+            | ArgumentConversionType::FromOptionalToUniquePtr => {
+                let innerty = self.make_unique_ptr_type();
+                parse_quote! {
+                    Option < #innerty >
+                }
+            }
+            ArgumentConversionType::FromSliceToOptional
+            | ArgumentConversionType::FromOptionalToValue => {
+                let innerty = &self.unwrapped_type;
+                parse_quote! {
+                    Option < #innerty >
+                }
+            }
+            ArgumentConversionType::FromUniquePtrToValue
+            | ArgumentConversionType::FromSliceToPointerAndLength(_)
+            | ArgumentConversionType::FromMutSliceToPointerAndLength(_)
+            | ArgumentConversionType::FromMutSliceToSpan => self.converted_rust_type(),
+            ArgumentConversionType::FromBoxToFunction(ref closure) => closure.as_ref().clone(),
+            ArgumentConversionType::FromStructToTuple(ref tuple)
+            | ArgumentConversionType::FromTupleToStruct(ref tuple) => {
+                let members = &tuple.1;
+                parse_quote! {
+                    (#(#members,)*)
+                }
+            }
+            _ => self.unconverted_rust_type(),
+        }
+    }
+
+    /// Converts between the type seen by users of any Rust wrapper
+    /// function and the type passed across the bridge: for parameters,
+    /// into the bridge; for return values, out of it.
+    pub(crate) fn rust_conversion(&self, expr: TokenStream) -> TokenStream {
+        let has_value = make_ident(HAS_VALUE_PARAM);
+        match self.conversion {
+            ArgumentConversionType::FromUniquePtrToOptional => quote! {
+                #expr.unwrap_or_else(cxx::UniquePtr::null)
+            },
+            ArgumentConversionType::FromBoxToFunction(_) => {
+                let callback = &self.unwrapped_type;
+                quote! {
+                    Box::new(#callback(Box::new(#expr)))
+                }
+            }
+            ArgumentConversionType::FromSliceToOptional => quote! {
+                #expr.as_ref().map_or(&[][..], std::slice::from_ref)
+            },
+            ArgumentConversionType::FromSliceToPointerAndLength(ref len)
+            | ArgumentConversionType::FromMutSliceToPointerAndLength(ref len) => quote! {
+                {
+                    let autocxx_gen_slice = #expr;
+                    assert!(
+                        <#len as std::convert::TryFrom<usize>>::try_from(autocxx_gen_slice.len()).is_ok(),
+                        "slice is too long for its length parameter"
+                    );
+                    autocxx_gen_slice
+                }
+            },
+            ArgumentConversionType::FromStructToTuple(ref tuple) => {
+                let tuple_struct = self.tuple_ident();
+                let fields: Vec<_> = (0..tuple.1.len()).map(tuple_field_name).collect();
+                quote! {
+                    {
+                        let (#(#fields,)*) = #expr;
+                        cxxbridge::#tuple_struct { #(#fields),* }
+                    }
+                }
+            }
+            ArgumentConversionType::FromTupleToStruct(ref tuple) => {
+                let fields = (0..tuple.1.len()).map(tuple_field_name);
+                quote! {
+                    {
+                        let autocxx_gen_tuple = #expr;
+                        (#(autocxx_gen_tuple.#fields,)*)
+                    }
+                }
+            }
+            ArgumentConversionType::FromOptionalToUniquePtr => quote! {
+                {
+                    let autocxx_gen_ptr = #expr;
+                    if autocxx_gen_ptr.is_null() {
+                        None
+                    } else {
+                        Some(autocxx_gen_ptr)
+                    }
+                }
+            },
+            // The expression passes a reference to #has_value to C++.
+            ArgumentConversionType::FromOptionalToValue => quote! {
+                {
+                    let mut #has_value = false;
+                    let autocxx_gen_value = #expr;
+                    if #has_value {
+                        Some(autocxx_gen_value)
+                    } else {
+                        None
+                    }
+                }
+            },
+            _ => expr,
+        }
+    }
+
+    /// The name within the `cxx::bridge` of the shared struct in which
+    /// a tuple crosses it.
+    fn tuple_ident(&self) -> Ident {
+        match &self.unwrapped_type {
+            Type::Path(typ) => typ.path.segments.last().unwrap().ident.clone(),
+            _ => panic!("Tuple struct wasn't a path"),
+        }
+    }
+
+    fn tuple_type_as_string(tuple: &(String, Vec<Type>)) -> Result<String, ConvertError> {
+        Ok(format!(
+            "{}<{}>",
+            tuple.0,
+            tuple
+                .1
+                .iter()
+                .map(type_to_cpp)
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", ")
+        ))
+    }
+
     fn unwrapped_type_as_string(&self) -> Result<String, ConvertError> {
         type_to_cpp(&self.unwrapped_type)
     }
@@ -109,9 +525,86 @@ impl ArgumentConversion {
                 self.unconverted_type()?,
                 var_name
             ),
+            ArgumentConversionType::FromUniquePtrToOptional => {
+                format!("autocxx_unique_ptr_to_optional(std::move({}))", var_name)
+            }
+            ArgumentConversionType::FromSliceToOptional => {
+                format!("autocxx_slice_to_optional({})", var_name)
+            }
+            ArgumentConversionType::FromOptionalToUniquePtr => {
+                format!("autocxx_optional_to_unique_ptr({})", var_name)
+            }
+            ArgumentConversionType::FromOptionalToValue => format!(
+                "autocxx_optional_to_value({}, {})",
+                var_name, HAS_VALUE_PARAM
+            ),
+            ArgumentConversionType::FromStrToStringView => {
+                format!("std::string_view({}.data(), {}.size())", var_name, var_name)
+            }
+            ArgumentConversionType::FromStringViewToBytes => format!(
+                "[](std::string_view s) {{ return rust::Slice<const uint8_t>(reinterpret_cast<const uint8_t*>(s.data()), s.size()); }}({})",
+                var_name
+            ),
+            ArgumentConversionType::FromArrayToPointer => format!("{}.data()", var_name),
+            // This provides both the pointer and the length parameters.
+            ArgumentConversionType::FromSliceToPointerAndLength(_)
+            | ArgumentConversionType::FromMutSliceToPointerAndLength(_) => {
+                format!("{}.data(), {}.size()", var_name, var_name)
+            }
+            ArgumentConversionType::FromMutSliceToSpan => format!(
+                "std::span<{}>({}.data(), {}.size())",
+                self.unwrapped_type_as_string()?,
+                var_name,
+                var_name
+            ),
+            ArgumentConversionType::FromStructToTuple(ref tuple) => format!(
+                "{}({})",
+                Self::tuple_type_as_string(tuple)?,
+                (0..tuple.1.len())
+                    .map(|i| format!("std::move({}.{})", var_name, tuple_field_name(i)))
+                    .join(", ")
+            ),
+            ArgumentConversionType::FromTupleToStruct(ref tuple) => format!(
+                "[]({} t) {{ return {}{{{}}}; }}({})",
+                Self::tuple_type_as_string(tuple)?,
+                self.unwrapped_type_as_string()?,
+                (0..tuple.1.len())
+                    .map(|i| format!("std::get<{}>(std::move(t))", i))
+                    .join(", "),
+                var_name
+            ),
+            // std::function must be copyable, whereas a rust::Box isn't, so
+            // copies share the box, and the closure is dropped along with
+            // the last of them.
+            ArgumentConversionType::FromBoxToFunction(_) => format!(
+                "[autocxx_gen_callback = std::make_shared<{}>(std::move({}))](auto&&... args) {{ return (*autocxx_gen_callback)->call(std::forward<decltype(args)>(args)...); }}",
+                self.unconverted_type()?,
+                var_name
+            ),
+            ArgumentConversionType::FromArrayToArrayReference => match &self.unwrapped_type {
+                Type::Reference(typr) => match typr.elem.as_ref() {
+                    Type::Array(typa) => format!(
+                        "*reinterpret_cast<{}{}(*)[{}]>({}.data())",
+                        if typr.mutability.is_some() {
+                            ""
+                        } else {
+                            "const "
+                        },
+                        type_to_cpp(typa.elem.as_ref())?,
+                        array_len(&typa.len).ok_or_else(|| self.unsupported())?,
+                        var_name
+                    ),
+                    _ => return Err(self.unsupported()),
+                },
+                _ => return Err(self.unsupported()),
+            },
         })
     }
 
+    fn unsupported(&self) -> ConvertError {
+        ConvertError::UnsupportedType(self.unwrapped_type.to_token_stream().to_string())
+    }
+
     fn make_unique_ptr_type(&self) -> Type {
         let innerty = &self.unwrapped_type;
         parse_quote! {
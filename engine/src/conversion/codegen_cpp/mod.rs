@@ -15,7 +15,8 @@
 pub(crate) mod function_wrapper;
 pub(crate) mod type_to_cpp;
 
-use crate::types::TypeName;
+use crate::{known_types::KNOWN_TYPES, types::TypeName};
+use indoc::indoc;
 use itertools::Itertools;
 use std::collections::HashSet;
 use syn::Type;
@@ -23,7 +24,7 @@ use syn::Type;
 use function_wrapper::FunctionWrapper;
 use type_to_cpp::type_to_cpp;
 
-use self::function_wrapper::FunctionWrapperPayload;
+use self::function_wrapper::{FunctionWrapperPayload, HAS_VALUE_PARAM};
 
 use super::{analysis::fun::FnAnalysis, api::Api, ConvertError};
 
@@ -34,27 +35,55 @@ pub(crate) enum AdditionalNeed {
     FunctionWrapper(Box<FunctionWrapper>),
     CTypeTypedef(TypeName),
     ConcreteTemplatedTypeTypedef(TypeName, Box<Type>),
+    PrimitiveWrapperTypedef(TypeName, Box<Type>),
+    CallbackDeclaration(TypeName),
+    TupleDeclaration(TypeName),
 }
 
+/// The name of a generated C++ function giving access to the
+/// contents of a concrete map, pair or tuple type, e.g. `len` or `first`.
+pub(crate) fn accessor_name(ty: &str, accessor: &str) -> String {
+    format!("{}_{}", ty, accessor)
+}
+
+/// The name of the C++ type we generate to iterate over a concrete
+/// map type.
+pub(crate) fn map_iterator_name(map: &str) -> String {
+    format!("{}Iter", map)
+}
+
+/// A header to be `#include`d, either by the C++ we generate or by
+/// the `cxx::bridge`.
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash)]
-struct Header {
-    name: &'static str,
+pub(crate) struct Header {
+    name: String,
     system: bool,
 }
 
 impl Header {
-    fn system(name: &'static str) -> Self {
-        Header { name, system: true }
+    pub(crate) fn system(name: &str) -> Self {
+        Header {
+            name: name.to_string(),
+            system: true,
+        }
     }
 
-    fn user(name: &'static str) -> Self {
+    pub(crate) fn user(name: &str) -> Self {
         Header {
-            name,
+            name: name.to_string(),
             system: false,
         }
     }
 
-    fn include_stmt(&self) -> String {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn is_system(&self) -> bool {
+        self.system
+    }
+
+    pub(crate) fn include_stmt(&self) -> String {
         if self.system {
             format!("#include <{}>", self.name)
         } else {
@@ -84,25 +113,49 @@ pub(crate) struct CppCodegenResults {
 /// generates, and perhaps we'll explore that in future. But for now,
 /// autocxx generates its own _additional_ C++ files which therefore
 /// need to be built and included in linking procedures.
-pub(crate) struct CppCodeGenerator {
+///
+/// If the user asked for any `define!`s, we always generate these files
+/// even if there are no functions within, because the cxx-generated C++
+/// relies on our header to set up the preprocessor before anything else
+/// is included.
+pub(crate) struct CppCodeGenerator<'a> {
     additional_functions: Vec<AdditionalFunction>,
     inclusions: String,
+    header_name: &'a str,
+    cxxgen_header_name: &'a str,
+    has_defines: bool,
+    generated_optional_helpers: bool,
+    uses_cxx_types: bool,
 }
 
-impl CppCodeGenerator {
+impl<'a> CppCodeGenerator<'a> {
     pub(crate) fn generate_cpp_code(
         inclusions: String,
+        header_name: &'a str,
+        cxxgen_header_name: &'a str,
         apis: &[Api<FnAnalysis>],
+        has_defines: bool,
     ) -> Result<Option<CppCodegenResults>, ConvertError> {
-        let mut gen = CppCodeGenerator::new(inclusions);
+        let mut gen =
+            CppCodeGenerator::new(inclusions, header_name, cxxgen_header_name, has_defines);
         gen.add_needs(apis.iter().filter_map(|api| api.additional_cpp()))?;
         Ok(gen.generate())
     }
 
-    fn new(inclusions: String) -> Self {
+    fn new(
+        inclusions: String,
+        header_name: &'a str,
+        cxxgen_header_name: &'a str,
+        has_defines: bool,
+    ) -> Self {
         CppCodeGenerator {
             additional_functions: Vec::new(),
             inclusions,
+            header_name,
+            cxxgen_header_name,
+            has_defines,
+            generated_optional_helpers: false,
+            uses_cxx_types: false,
         }
     }
 
@@ -118,7 +171,32 @@ impl CppCodeGenerator {
                 }
                 AdditionalNeed::CTypeTypedef(tn) => self.generate_ctype_typedef(&tn),
                 AdditionalNeed::ConcreteTemplatedTypeTypedef(tn, def) => {
-                    self.generate_typedef(&tn, type_to_cpp(&def)?)
+                    self.generate_typedef(&tn, type_to_cpp(&def)?);
+                    if let Some((key, value)) = KNOWN_TYPES.get_map_key_and_value(&def) {
+                        self.generate_map_accessors(&tn, key, value)?
+                    }
+                    if let Some((first, second)) = KNOWN_TYPES.get_pair_members(&def) {
+                        self.generate_pair_accessors(
+                            &tn,
+                            &type_to_cpp(first)?,
+                            &type_to_cpp(second)?,
+                        )
+                    }
+                    if let Some(members) = KNOWN_TYPES.get_tuple_members(&def) {
+                        self.generate_tuple_accessors(
+                            &tn,
+                            &members
+                                .into_iter()
+                                .map(type_to_cpp)
+                                .collect::<Result<Vec<_>, _>>()?,
+                        )
+                    }
+                }
+                AdditionalNeed::PrimitiveWrapperTypedef(tn, inner) => {
+                    self.generate_typedef(&tn, type_to_cpp(&inner)?)
+                }
+                AdditionalNeed::CallbackDeclaration(tn) | AdditionalNeed::TupleDeclaration(tn) => {
+                    self.generate_cxx_type_declaration(&tn)
                 }
             }
         }
@@ -126,7 +204,7 @@ impl CppCodeGenerator {
     }
 
     fn generate(&self) -> Option<CppCodegenResults> {
-        if self.additional_functions.is_empty() {
+        if self.additional_functions.is_empty() && !self.has_defines {
             None
         } else {
             let headers: HashSet<Header> = self
@@ -143,7 +221,16 @@ impl CppCodeGenerator {
                 headers, self.inclusions, type_definitions, declarations
             );
             let definitions = self.concat_additional_items(|x| &x.definition);
-            let definitions = format!("#include \"autocxxgen.h\"\n{}", definitions);
+            // Callbacks and tuples are declared by cxx, in its generated header.
+            let cxxgen_include = if self.uses_cxx_types {
+                format!("#include \"{}\"\n", self.cxxgen_header_name)
+            } else {
+                "".to_string()
+            };
+            let definitions = format!(
+                "#include \"{}\"\n{}{}",
+                self.header_name, cxxgen_include, definitions
+            );
             Some(CppCodegenResults {
                 declarations,
                 definitions,
@@ -208,6 +295,14 @@ impl CppCodeGenerator {
                 format!("arg{}", counter)
             }
         };
+        if details
+            .argument_conversion
+            .iter()
+            .chain(details.return_conversion.iter())
+            .any(|conv| conv.is_optional())
+        {
+            self.generate_optional_helpers();
+        }
         let args: Result<Vec<_>, _> = details
             .argument_conversion
             .iter()
@@ -220,7 +315,15 @@ impl CppCodeGenerator {
                 ))
             })
             .collect();
-        let args = args?.join(", ");
+        let mut args = args?;
+        if details
+            .return_conversion
+            .iter()
+            .any(|x| x.needs_has_value_param())
+        {
+            args.push(format!("bool& {}", HAS_VALUE_PARAM));
+        }
+        let args = args.join(", ");
         let ret_type = details
             .return_conversion
             .as_ref()
@@ -263,15 +366,283 @@ impl CppCodeGenerator {
         };
         let definition = format!("{} {{ {}; }}", declaration, underlying_function_call,);
         let declaration = format!("{};", declaration);
+        let mut headers = vec![Header::system("memory")];
+        if details
+            .argument_conversion
+            .iter()
+            .any(|conv| conv.is_array())
+        {
+            headers.push(Header::system("array"));
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .any(|conv| conv.is_slice())
+        {
+            headers.push(Header::user("cxx.h"));
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .any(|conv| conv.is_span())
+        {
+            headers.push(Header::system("span"));
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .any(|conv| conv.is_function())
+        {
+            headers.push(Header::system("functional"));
+            headers.push(Header::user("cxx.h"));
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .chain(details.return_conversion.iter())
+            .any(|conv| conv.is_string_view())
+        {
+            headers.push(Header::system("string_view"));
+            headers.push(Header::user("cxx.h"));
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .chain(details.return_conversion.iter())
+            .any(|conv| conv.is_tuple())
+        {
+            headers.push(Header::system("tuple"));
+            headers.push(Header::system("utility"));
+        }
         self.additional_functions.push(AdditionalFunction {
             type_definition: "".into(),
             declaration,
             definition,
-            headers: vec![Header::system("memory")],
+            headers,
+        });
+        Ok(())
+    }
+
+    /// Helpers used by wrapper functions to convert std::optionals
+    /// to and from things which cxx can pass across the bridge. When
+    /// returning an empty optional of a type passed by value, we must
+    /// still return something, so if the type has no default constructor
+    /// we return zeroed storage instead. Such types are trivial, since
+    /// cxx passes them by value, so this creates one without running any
+    /// constructor.
+    fn generate_optional_helpers(&mut self) {
+        if self.generated_optional_helpers {
+            return;
+        }
+        self.generated_optional_helpers = true;
+        let type_definition = indoc! {"
+            template <typename T> std::unique_ptr<T> autocxx_optional_to_unique_ptr(std::optional<T> o) {
+                return o ? std::make_unique<T>(std::move(*o)) : nullptr;
+            }
+            template <typename T> T autocxx_optional_to_value(std::optional<T> o, bool& has_value) {
+                has_value = o.has_value();
+                if (o) {
+                    return std::move(*o);
+                }
+                if constexpr (std::is_default_constructible_v<T>) {
+                    return T{};
+                } else {
+                    alignas(T) unsigned char storage[sizeof(T)] = {};
+                    return *std::launder(reinterpret_cast<T*>(storage));
+                }
+            }
+            template <typename T> std::optional<T> autocxx_unique_ptr_to_optional(std::unique_ptr<T> p) {
+                return p ? std::optional<T>(std::move(*p)) : std::nullopt;
+            }
+            template <typename T> std::optional<T> autocxx_slice_to_optional(rust::Slice<const T> s) {
+                return s.size() ? std::optional<T>(*s.data()) : std::nullopt;
+            }
+        "};
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: type_definition.into(),
+            declaration: "".into(),
+            definition: "".into(),
+            headers: vec![
+                Header::system("memory"),
+                Header::system("new"),
+                Header::system("optional"),
+                Header::system("type_traits"),
+                Header::user("cxx.h"),
+            ],
+        })
+    }
+
+    /// Functions giving access to the contents of a concrete map type,
+    /// since cxx knows nothing about maps. Primitive keys and values
+    /// are copied into the map; anything else is moved out of a
+    /// `std::unique_ptr`, since it may not be copyable.
+    fn generate_map_accessors(
+        &mut self,
+        tn: &TypeName,
+        key_type: &Type,
+        value_type: &Type,
+    ) -> Result<(), ConvertError> {
+        let map = tn.get_final_ident();
+        let iter = map_iterator_name(map);
+        let name = |accessor| accessor_name(map, accessor);
+        let key = type_to_cpp(key_type)?;
+        let value = type_to_cpp(value_type)?;
+        let insertion_param = |ty: &Type, cpp: &str, param: &str| {
+            if KNOWN_TYPES.is_primitive(ty) {
+                (format!("const {}& {}", cpp, param), param.to_string())
+            } else {
+                (
+                    format!("std::unique_ptr<{}> {}", cpp, param),
+                    format!("std::move(*{})", param),
+                )
+            }
+        };
+        let (key_param, key_arg) = insertion_param(key_type, &key, "key");
+        let (value_param, value_arg) = insertion_param(value_type, &value, "value");
+        let key_ref = if KNOWN_TYPES.is_primitive(key_type) {
+            "key"
+        } else {
+            "*key"
+        };
+        let functions = [
+            (
+                format!("size_t {}(const {}& m)", name("len"), map),
+                "return m.size();".to_string(),
+            ),
+            (
+                format!(
+                    "bool {}(const {}& m, const {}& key)",
+                    name("contains"),
+                    map,
+                    key
+                ),
+                "return m.count(key) != 0;".to_string(),
+            ),
+            (
+                format!(
+                    "const {}* {}(const {}& m, const {}& key)",
+                    value,
+                    name("get"),
+                    map,
+                    key
+                ),
+                "auto it = m.find(key); return it == m.end() ? nullptr : &it->second;".to_string(),
+            ),
+            (
+                format!(
+                    "void {}({}& m, {}, {})",
+                    name("insert"),
+                    map,
+                    key_param,
+                    value_param
+                ),
+                format!(
+                    "m.erase({}); m.emplace({}, {});",
+                    key_ref, key_arg, value_arg
+                ),
+            ),
+            (
+                format!(
+                    "std::unique_ptr<{}> {}(const {}& m)",
+                    iter,
+                    name("begin"),
+                    map
+                ),
+                format!(
+                    "return std::make_unique<{}>({}{{m.begin(), m.end()}});",
+                    iter, iter
+                ),
+            ),
+            (
+                format!("bool {}(const {}& it)", name("done"), iter),
+                "return it.current == it.end;".to_string(),
+            ),
+            (
+                format!("const {}& {}(const {}& it)", key, name("key"), iter),
+                "return it.current->first;".to_string(),
+            ),
+            (
+                format!("const {}& {}(const {}& it)", value, name("value"), iter),
+                "return it.current->second;".to_string(),
+            ),
+            (
+                format!("void {}({}& it)", name("advance"), iter),
+                "++it.current;".to_string(),
+            ),
+        ];
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: format!(
+                "struct {} {{ {}::const_iterator current; {}::const_iterator end; }};",
+                iter, map, map
+            ),
+            declaration: "".into(),
+            definition: "".into(),
+            headers: Vec::new(),
         });
+        for (declaration, body) in functions.iter() {
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: "".into(),
+                declaration: format!("{};", declaration),
+                definition: format!("{} {{ {} }}", declaration, body),
+                headers: vec![Header::system("memory")],
+            })
+        }
         Ok(())
     }
 
+    /// Functions giving access to the members of a concrete pair type
+    /// which can't be passed by value as a Rust tuple.
+    fn generate_pair_accessors(&mut self, tn: &TypeName, first: &str, second: &str) {
+        let pair = tn.get_final_ident();
+        for (accessor, ty) in [("first", first), ("second", second)].iter() {
+            let declaration = format!(
+                "const {}& {}(const {}& p)",
+                ty,
+                accessor_name(pair, accessor),
+                pair
+            );
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: "".into(),
+                declaration: format!("{};", declaration),
+                definition: format!("{} {{ return p.{}; }}", declaration, accessor),
+                headers: vec![Header::system("utility")],
+            })
+        }
+    }
+
+    /// Functions giving access to the members of a concrete tuple type
+    /// which can't be passed by value as a Rust tuple.
+    fn generate_tuple_accessors(&mut self, tn: &TypeName, members: &[String]) {
+        let tuple = tn.get_final_ident();
+        for (i, ty) in members.iter().enumerate() {
+            let declaration = format!(
+                "const {}& {}(const {}& t)",
+                ty,
+                accessor_name(tuple, &format!("get{}", i)),
+                tuple
+            );
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: "".into(),
+                declaration: format!("{};", declaration),
+                definition: format!("{} {{ return std::get<{}>(t); }}", declaration, i),
+                headers: vec![Header::system("tuple")],
+            })
+        }
+    }
+
+    /// Some types, such as those which hold Rust closures, are defined
+    /// by cxx, but our wrapper functions need to refer to them before
+    /// that happens.
+    fn generate_cxx_type_declaration(&mut self, tn: &TypeName) {
+        self.uses_cxx_types = true;
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: format!("struct {};", tn.get_final_ident()),
+            declaration: "".into(),
+            definition: "".into(),
+            headers: Vec::new(),
+        })
+    }
+
     fn generate_ctype_typedef(&mut self, tn: &TypeName) {
         let cpp_name = tn.to_cpp_name();
         self.generate_typedef(tn, cpp_name)
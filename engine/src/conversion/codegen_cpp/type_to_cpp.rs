@@ -14,7 +14,7 @@
 
 use crate::{conversion::ConvertError, types::TypeName};
 use quote::ToTokens;
-use syn::{Token, Type};
+use syn::{Expr, ExprLit, Lit, Token, Type};
 
 pub(crate) fn type_to_cpp(ty: &Type) -> Result<String, ConvertError> {
     match ty {
@@ -63,8 +63,14 @@ pub(crate) fn type_to_cpp(ty: &Type) -> Result<String, ConvertError> {
             get_mut_string(&typp.mutability),
             type_to_cpp(typp.elem.as_ref())?
         )),
-        Type::Array(_)
-        | Type::BareFn(_)
+        // cxx represents Rust arrays as std::array in C++.
+        Type::Array(typa) => Ok(format!(
+            "std::array<{}, {}>",
+            type_to_cpp(typa.elem.as_ref())?,
+            array_len(&typa.len)
+                .ok_or_else(|| ConvertError::UnsupportedType(ty.to_token_stream().to_string()))?
+        )),
+        Type::BareFn(_)
         | Type::Group(_)
         | Type::ImplTrait(_)
         | Type::Infer(_)
@@ -81,6 +87,16 @@ pub(crate) fn type_to_cpp(ty: &Type) -> Result<String, ConvertError> {
     }
 }
 
+/// The length of an array, as given by bindgen, e.g. `4usize`.
+pub(crate) fn array_len(len: &Expr) -> Option<String> {
+    match len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => Some(lit.base10_digits().to_string()),
+        _ => None,
+    }
+}
+
 fn get_mut_string(mutability: &Option<Token![mut]>) -> &'static str {
     match mutability {
         None => "const ",
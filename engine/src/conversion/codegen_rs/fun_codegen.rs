@@ -12,10 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{
     parse::Parser, parse_quote, punctuated::Punctuated, token::Unsafe, Attribute, FnArg,
-    ForeignItem, Ident, ImplItem, ReturnType,
+    ForeignItem, Ident, ImplItem, Item, ReturnType,
 };
 
 use super::{
@@ -27,6 +28,7 @@ use crate::{
     conversion::{
         analysis::fun::{ArgumentAnalysis, FnAnalysisBody},
         api::ImplBlockDetails,
+        codegen_cpp::function_wrapper::{ArgumentConversion, HAS_VALUE_PARAM},
     },
     types::{Namespace, TypeName},
 };
@@ -37,33 +39,42 @@ pub(crate) fn gen_function(ns: &Namespace, analysis: FnAnalysisBody) -> RsCodege
     let rust_name = analysis.rust_name;
     let self_ty = analysis.self_ty;
     let ret_type = analysis.ret_type;
+    let ret_conversion = analysis.ret_conversion;
     let is_constructor = analysis.is_constructor;
     let param_details = analysis.param_details;
     let cpp_call_name = analysis.cpp_call_name;
     let wrapper_function_needed = analysis.wrapper_function_needed;
+    let rust_wrapper_needed = analysis.rust_wrapper_needed;
     let requires_unsafe = analysis.requires_unsafe;
     let params = analysis.params;
     let vis = analysis.vis;
 
     let mut cpp_name_attr = Vec::new();
     let mut impl_entry = None;
+    let mut bindgen_mod_item = None;
     let unsafety: Option<Unsafe> = if requires_unsafe {
         Some(parse_quote!(unsafe))
     } else {
         None
     };
     if cxxbridge_name != rust_name {
+        let wrapper = RustWrapper {
+            param_details: &param_details,
+            is_constructor,
+            cxxbridge_name: &cxxbridge_name,
+            rust_name: &rust_name,
+            ret_type: &ret_type,
+            ret_conversion: ret_conversion.as_ref(),
+            unsafety: &unsafety,
+        };
         if let Some(type_name) = &self_ty {
             // Method, or static method.
-            impl_entry = Some(generate_method_impl(
-                &param_details,
-                is_constructor,
-                type_name,
-                &cxxbridge_name,
-                &rust_name,
-                &ret_type,
-                &unsafety,
-            ));
+            impl_entry = Some(wrapper.generate_method_impl(type_name));
+        } else if rust_wrapper_needed {
+            // A plain function which needs some conversions on the
+            // Rust side. This lives alongside the bindgen types, and
+            // is pulled into the output mod from there.
+            bindgen_mod_item = Some(wrapper.generate_function());
         }
     }
     let rust_name_attr: Vec<_> = if rename_using_rust_attr {
@@ -115,43 +126,76 @@ pub(crate) fn gen_function(ns: &Namespace, analysis: FnAnalysisBody) -> RsCodege
         extern_c_mod_item: Some(extern_c_mod_item),
         bridge_items: Vec::new(),
         global_items: Vec::new(),
-        bindgen_mod_item: None,
+        bindgen_mod_item,
         impl_entry,
     }
 }
 
-/// Generate an 'impl Type { methods-go-here }' item
-fn generate_method_impl(
-    param_details: &[ArgumentAnalysis],
+/// A function in Rust which calls through to a function in the
+/// `cxx::bridge`.
+struct RustWrapper<'a> {
+    param_details: &'a [ArgumentAnalysis],
     is_constructor: bool,
-    impl_block_type_name: &TypeName,
-    cxxbridge_name: &Ident,
-    rust_name: &str,
-    ret_type: &ReturnType,
-    unsafety: &Option<Unsafe>,
-) -> Box<ImplBlockDetails> {
-    let mut wrapper_params: Punctuated<FnArg, syn::Token![,]> = Punctuated::new();
-    let mut arg_list = Vec::new();
-    for pd in param_details {
-        let type_name = pd.conversion.converted_rust_type();
-        let wrapper_arg_name = if pd.self_type.is_some() && !is_constructor {
-            parse_quote!(self)
-        } else {
-            pd.name.clone()
-        };
-        wrapper_params.push(parse_quote!(
-            #wrapper_arg_name: #type_name
-        ));
-        arg_list.push(wrapper_arg_name);
+    cxxbridge_name: &'a Ident,
+    rust_name: &'a str,
+    ret_type: &'a ReturnType,
+    ret_conversion: Option<&'a ArgumentConversion>,
+    unsafety: &'a Option<Unsafe>,
+}
+
+impl<'a> RustWrapper<'a> {
+    /// Generate an 'impl Type { methods-go-here }' item
+    fn generate_method_impl(&self, impl_block_type_name: &TypeName) -> Box<ImplBlockDetails> {
+        Box::new(ImplBlockDetails {
+            item: ImplItem::Method(syn::parse2(self.generate_tokens()).unwrap()),
+            ty: make_ident(impl_block_type_name.get_final_ident()),
+        })
+    }
+
+    fn generate_function(&self) -> Item {
+        Item::Fn(syn::parse2(self.generate_tokens()).unwrap())
     }
 
-    let rust_name = make_ident(&rust_name);
-    Box::new(ImplBlockDetails {
-        item: ImplItem::Method(parse_quote! {
+    fn generate_tokens(&self) -> TokenStream {
+        let mut wrapper_params: Punctuated<FnArg, syn::Token![,]> = Punctuated::new();
+        let mut arg_list = Vec::new();
+        for pd in self.param_details {
+            let type_name = pd.conversion.outer_rust_type();
+            let wrapper_arg_name = if pd.self_type.is_some() && !self.is_constructor {
+                parse_quote!(self)
+            } else {
+                pd.name.clone()
+            };
+            wrapper_params.push(parse_quote!(
+                #wrapper_arg_name: #type_name
+            ));
+            arg_list.push(pd.conversion.rust_conversion(quote! { #wrapper_arg_name }));
+        }
+        let mut ret_type = self.ret_type.clone();
+        let cxxbridge_name = self.cxxbridge_name;
+        let mut call = quote! {
+            cxxbridge::#cxxbridge_name ( #(#arg_list),* )
+        };
+        if let Some(ret_conversion) = self.ret_conversion {
+            if ret_conversion.rust_work_needed() {
+                if ret_conversion.needs_has_value_param() {
+                    let has_value = make_ident(HAS_VALUE_PARAM);
+                    arg_list.push(quote! { &mut #has_value });
+                    call = quote! {
+                        cxxbridge::#cxxbridge_name ( #(#arg_list),* )
+                    };
+                }
+                let outer_ret_type = ret_conversion.outer_rust_type();
+                ret_type = parse_quote! { -> #outer_ret_type };
+                call = ret_conversion.rust_conversion(call);
+            }
+        }
+        let rust_name = make_ident(self.rust_name);
+        let unsafety = self.unsafety;
+        quote! {
             pub #unsafety fn #rust_name ( #wrapper_params ) #ret_type {
-                cxxbridge::#cxxbridge_name ( #(#arg_list),* )
+                #call
             }
-        }),
-        ty: make_ident(impl_block_type_name.get_final_ident()),
-    })
+        }
+    }
 }
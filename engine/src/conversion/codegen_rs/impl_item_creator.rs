@@ -22,5 +22,13 @@ pub(crate) fn create_impl_items(id: &Ident) -> Vec<Item> {
         Item::Impl(parse_quote! {
             impl CxxVector<#id> {}
         }),
+        // So that SharedPtr::downgrade and WeakPtr::upgrade are available
+        // even if no function we've bound uses them.
+        Item::Impl(parse_quote! {
+            impl SharedPtr<#id> {}
+        }),
+        Item::Impl(parse_quote! {
+            impl WeakPtr<#id> {}
+        }),
     ]
 }
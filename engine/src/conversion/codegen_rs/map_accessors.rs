@@ -0,0 +1,143 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{parse_quote, ImplItem, Type};
+
+use crate::{
+    conversion::codegen_cpp::{accessor_name, map_iterator_name},
+    known_types::KNOWN_TYPES,
+    types::make_ident,
+};
+
+use super::unqualify::unqualify_type;
+
+/// Primitive keys and values are copied into a map, but anything else
+/// must be moved in from a `UniquePtr`, since it may not be copyable.
+fn insertion_type(ty: &Type, within_bridge: bool) -> Type {
+    let inner = if within_bridge {
+        unqualify_type(ty.clone())
+    } else {
+        ty.clone()
+    };
+    match (KNOWN_TYPES.is_primitive(ty), within_bridge) {
+        (true, _) => parse_quote! { &#inner },
+        (false, true) => parse_quote! { UniquePtr<#inner> },
+        (false, false) => parse_quote! { cxx::UniquePtr<#inner> },
+    }
+}
+
+/// Declarations within the `cxx::bridge` of the C++ functions which
+/// give access to the contents of a concrete map type, along with the
+/// C++ type we use to iterate over it.
+pub(crate) fn map_accessor_declarations(map: &Ident, key: &Type, value: &Type) -> TokenStream {
+    let name = |accessor| make_ident(accessor_name(&map.to_string(), accessor));
+    let iter = make_ident(map_iterator_name(&map.to_string()));
+    let (len, contains, get, insert) = (name("len"), name("contains"), name("get"), name("insert"));
+    let (begin, done, iter_key, iter_value, advance) = (
+        name("begin"),
+        name("done"),
+        name("key"),
+        name("value"),
+        name("advance"),
+    );
+    let key_insertion_type = insertion_type(key, true);
+    let value_insertion_type = insertion_type(value, true);
+    let key = unqualify_type(key.clone());
+    let value = unqualify_type(value.clone());
+    quote! {
+        type #iter;
+        fn #len(m: &#map) -> usize;
+        fn #contains(m: &#map, key: &#key) -> bool;
+        fn #get(m: &#map, key: &#key) -> *const #value;
+        fn #insert(m: Pin<&mut #map>, key: #key_insertion_type, value: #value_insertion_type);
+        fn #begin(m: &#map) -> UniquePtr<#iter>;
+        fn #done(it: &#iter) -> bool;
+        unsafe fn #iter_key<'a>(it: &#iter) -> &'a #key;
+        unsafe fn #iter_value<'a>(it: &#iter) -> &'a #value;
+        fn #advance(it: Pin<&mut #iter>);
+    }
+}
+
+/// Rust methods on a concrete map type, built on top of the C++
+/// accessor functions. These live within the bindgen mod, so the key and
+/// value types can be referred to exactly as bindgen did.
+pub(crate) fn map_accessor_methods(map: &Ident, key: &Type, value: &Type) -> ImplItem {
+    let name = |accessor| make_ident(accessor_name(&map.to_string(), accessor));
+    let (len, contains, get, insert) = (name("len"), name("contains"), name("get"), name("insert"));
+    let (begin, done, iter_key, iter_value, advance) = (
+        name("begin"),
+        name("done"),
+        name("key"),
+        name("value"),
+        name("advance"),
+    );
+    let key_insertion_type = insertion_type(key, false);
+    let value_insertion_type = insertion_type(value, false);
+    let null_checks = [
+        (KNOWN_TYPES.is_primitive(key), quote! { key }),
+        (KNOWN_TYPES.is_primitive(value), quote! { value }),
+    ]
+    .iter()
+    .filter(|(is_primitive, _)| !is_primitive)
+    .map(|(_, param)| {
+        quote! {
+            assert!(!#param.is_null(), "Can't insert a null UniquePtr into a map");
+        }
+    })
+    .collect::<Vec<_>>();
+    ImplItem::Verbatim(quote! {
+        pub fn len(&self) -> usize {
+            cxxbridge::#len(self)
+        }
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+        pub fn contains(&self, key: &#key) -> bool {
+            cxxbridge::#contains(self, key)
+        }
+        pub fn get(&self, key: &#key) -> Option<&#value> {
+            // The value, if any, lives as long as the map is borrowed.
+            unsafe { cxxbridge::#get(self, key).as_ref() }
+        }
+        /// Inserts this key and value, replacing any existing value for
+        /// this key.
+        pub fn insert(
+            self: std::pin::Pin<&mut Self>,
+            key: #key_insertion_type,
+            value: #value_insertion_type,
+        ) {
+            #(#null_checks)*
+            cxxbridge::#insert(self, key, value)
+        }
+        pub fn iter(&self) -> impl Iterator<Item = (&#key, &#value)> + '_ {
+            let mut it = cxxbridge::#begin(self);
+            std::iter::from_fn(move || {
+                if cxxbridge::#done(&it) {
+                    None
+                } else {
+                    // The map can't be modified while we borrow it, so
+                    // its entries outlive the iterator.
+                    let entry = unsafe { (cxxbridge::#iter_key(&it), cxxbridge::#iter_value(&it)) };
+                    cxxbridge::#advance(it.pin_mut());
+                    Some(entry)
+                }
+            })
+        }
+        pub fn keys(&self) -> impl Iterator<Item = &#key> + '_ {
+            self.iter().map(|(key, _)| key)
+        }
+    })
+}
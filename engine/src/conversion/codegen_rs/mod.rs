@@ -14,25 +14,36 @@
 
 mod fun_codegen;
 mod impl_item_creator;
+mod map_accessors;
 mod namespace_organizer;
 mod non_pod_struct;
+mod pair_accessors;
+mod tuple_accessors;
 mod unqualify;
 
 use std::collections::HashMap;
 
 // The following should not need to be exposed outside
 // codegen_rs but currently Rust codegen happens everywhere... TODO
-pub(crate) use non_pod_struct::make_non_pod;
+pub(crate) use non_pod_struct::{make_non_pod, new_non_pod_struct};
 
-use syn::{parse_quote, ForeignItem, Ident, Item, ItemForeignMod, ItemMod};
+use syn::{
+    parse_quote, ForeignItem, Ident, ImplItem, Item, ItemForeignMod, ItemMod, ReturnType, Type,
+};
 
-use crate::types::{make_ident, Namespace};
+use crate::{
+    known_types::KNOWN_TYPES,
+    types::{make_ident, Namespace},
+};
 use impl_item_creator::create_impl_items;
 
 use self::{
     fun_codegen::gen_function,
+    map_accessors::{map_accessor_declarations, map_accessor_methods},
     namespace_organizer::{HasNs, NamespaceEntries},
-    non_pod_struct::new_non_pod_struct,
+    pair_accessors::{pair_accessor_declarations, pair_accessor_methods},
+    tuple_accessors::{tuple_accessor_declarations, tuple_accessor_methods, tuple_element_impls},
+    unqualify::{unqualify_ret_type, unqualify_type},
 };
 
 use super::{
@@ -40,7 +51,10 @@ use super::{
     api::{
         Api, ApiAnalysis, ApiDetail, ImplBlockDetails, TypeApiDetails, TypeKind, TypedefKind, Use,
     },
+    codegen_cpp::{function_wrapper::tuple_field_name, AdditionalNeed, Header},
+    ConvertError,
 };
+use proc_macro2::TokenStream;
 use quote::quote;
 
 unzip_n::unzip_n!(pub 3);
@@ -53,25 +67,31 @@ fn remove_nones<T>(input: Vec<Option<T>>) -> Vec<T> {
 /// In practice, much of the "generation" involves connecting together
 /// existing lumps of code within the Api structures.
 pub(crate) struct RsCodeGenerator<'a> {
-    include_list: &'a [String],
+    include_list: &'a [Header],
+    autocxxgen_header_name: &'a str,
     bindgen_mod: ItemMod,
+    has_defines: bool,
 }
 
 impl<'a> RsCodeGenerator<'a> {
     /// Generate code for a set of APIs that was discovered during parsing.
     pub(crate) fn generate_rs_code(
         all_apis: Vec<Api<FnAnalysis>>,
-        include_list: &'a [String],
+        include_list: &'a [Header],
+        autocxxgen_header_name: &'a str,
         bindgen_mod: ItemMod,
-    ) -> Vec<Item> {
+        has_defines: bool,
+    ) -> Result<Vec<Item>, ConvertError> {
         let c = Self {
             include_list,
+            autocxxgen_header_name,
             bindgen_mod,
+            has_defines,
         };
         c.rs_codegen(all_apis)
     }
 
-    fn rs_codegen(mut self, all_apis: Vec<Api<FnAnalysis>>) -> Vec<Item> {
+    fn rs_codegen(mut self, all_apis: Vec<Api<FnAnalysis>>) -> Result<Vec<Item>, ConvertError> {
         // ... and now let's start to generate the output code.
         // First, the hierarchy of mods containing lots of 'use' statements
         // which is the final API exposed as 'ffi'.
@@ -104,7 +124,7 @@ impl<'a> RsCodeGenerator<'a> {
         let mut all_items: Vec<Item> = all_items.into_iter().flatten().collect();
         // And finally any C++ we need to generate. And by "we" I mean autocxx not cxx.
         let has_additional_cpp_needs = additional_cpp_needs.into_iter().any(std::convert::identity);
-        extern_c_mod_items.extend(self.build_include_foreign_items(has_additional_cpp_needs));
+        extern_c_mod_items.extend(self.build_include_foreign_items(has_additional_cpp_needs)?);
         // We will always create an extern "C" mod even if bindgen
         // didn't generate one, e.g. because it only generated types.
         // We still want cxx to know about those types.
@@ -132,7 +152,7 @@ impl<'a> RsCodeGenerator<'a> {
             }
         }));
         all_items.append(&mut use_statements);
-        all_items
+        Ok(all_items)
     }
 
     fn make_foreign_mod_unsafe(ifm: ItemForeignMod) -> Item {
@@ -143,20 +163,43 @@ impl<'a> RsCodeGenerator<'a> {
         })
     }
 
-    fn build_include_foreign_items(&self, has_additional_cpp_needs: bool) -> Vec<ForeignItem> {
-        let extra_inclusion = if has_additional_cpp_needs {
-            Some("autocxxgen.h".to_string())
+    fn build_include_foreign_items(
+        &self,
+        has_additional_cpp_needs: bool,
+    ) -> Result<Vec<ForeignItem>, ConvertError> {
+        // autocxxgen.h carries any define!s, so if there are some, it must
+        // be included before any of the user's headers.
+        let autocxxgen = Header::user(self.autocxxgen_header_name);
+        let (defines_inclusion, extra_inclusion) = if self.has_defines {
+            (Some(autocxxgen), None)
+        } else if has_additional_cpp_needs {
+            (None, Some(autocxxgen))
         } else {
-            None
+            (None, None)
         };
-        let chained = self.include_list.iter().chain(extra_inclusion.iter());
-        chained
-            .map(|inc| {
-                ForeignItem::Macro(parse_quote! {
-                    include!(#inc);
-                })
-            })
-            .collect()
+        let chained = defines_inclusion
+            .iter()
+            .chain(self.include_list.iter())
+            .chain(extra_inclusion.iter());
+        chained.map(Self::generate_include).collect()
+    }
+
+    fn generate_include(header: &Header) -> Result<ForeignItem, ConvertError> {
+        let name = header.name();
+        if header.is_system() {
+            // cxx wants include!(<path>), where the path is a series of
+            // tokens rather than a string literal.
+            let path: TokenStream = name
+                .parse()
+                .map_err(|_| ConvertError::InvalidSystemHeader(name.to_string()))?;
+            Ok(ForeignItem::Macro(parse_quote! {
+                include!(<#path>);
+            }))
+        } else {
+            Ok(ForeignItem::Macro(parse_quote! {
+                include!(#name);
+            }))
+        }
     }
 
     /// Generate lots of 'use' statements to pull cxxbridge items into the output
@@ -306,19 +349,84 @@ impl<'a> RsCodeGenerator<'a> {
                 bindgen_mod_item: None,
                 impl_entry: None,
             },
-            ApiDetail::ConcreteType { ty_details, .. } => {
+            ApiDetail::ConcreteType {
+                ty_details,
+                additional_cpp,
+            } => {
                 let global_items = Self::generate_extern_type_impl(TypeKind::NonPod, &ty_details);
                 let final_ident = &ty_details.final_ident;
+                let def = match &additional_cpp {
+                    AdditionalNeed::ConcreteTemplatedTypeTypedef(_, def) => Some(def.as_ref()),
+                    _ => None,
+                };
+                let accessors = def.and_then(|def| {
+                    if let Some((key, value)) = KNOWN_TYPES.get_map_key_and_value(def) {
+                        Some((
+                            map_accessor_declarations(final_ident, key, value),
+                            map_accessor_methods(final_ident, key, value),
+                            Vec::new(),
+                        ))
+                    } else if let Some((first, second)) = KNOWN_TYPES.get_pair_members(def) {
+                        Some((
+                            pair_accessor_declarations(final_ident, first, second),
+                            pair_accessor_methods(final_ident, first, second),
+                            Vec::new(),
+                        ))
+                    } else {
+                        KNOWN_TYPES.get_tuple_members(def).map(|members| {
+                            (
+                                tuple_accessor_declarations(final_ident, &members),
+                                tuple_accessor_methods(),
+                                tuple_element_impls(final_ident, &members),
+                            )
+                        })
+                    }
+                });
+                let (accessor_declarations, impl_entry, trait_impls) = match accessors {
+                    Some((declarations, methods, trait_impls)) => (
+                        Some(declarations),
+                        Some(Box::new(ImplBlockDetails {
+                            item: methods,
+                            ty: final_ident.clone(),
+                        })),
+                        trait_impls,
+                    ),
+                    None => (None, None, Vec::new()),
+                };
+                let non_pod_struct = new_non_pod_struct(ty_details.final_ident.clone());
                 RsCodegenResult {
                     global_items,
                     bridge_items: create_impl_items(&final_ident),
                     extern_c_mod_item: Some(ForeignItem::Verbatim(quote! {
                         type #final_ident = super::bindgen::root::#final_ident;
+                        #accessor_declarations
                     })),
-                    bindgen_mod_item: Some(Item::Struct(new_non_pod_struct(
-                        ty_details.final_ident,
-                    ))),
+                    bindgen_mod_item: Some(Item::Verbatim(quote! {
+                        #non_pod_struct
+                        #(#trait_impls)*
+                    })),
+                    impl_entry,
+                }
+            }
+            ApiDetail::PrimitiveWrapper { ty_details, inner } => {
+                Self::generate_primitive_wrapper(&ty_details, inner)
+            }
+            ApiDetail::Callback { params, ret_type } => {
+                Self::generate_callback(id, params, ret_type)
+            }
+            ApiDetail::Tuple { members } => {
+                let fields = (0..members.len()).map(tuple_field_name);
+                let members = members.into_iter().map(unqualify_type);
+                RsCodegenResult {
+                    global_items: Vec::new(),
                     impl_entry: None,
+                    bridge_items: vec![Item::Struct(parse_quote! {
+                        pub struct #id {
+                            #(pub #fields: #members,)*
+                        }
+                    })],
+                    extern_c_mod_item: None,
+                    bindgen_mod_item: None,
                 }
             }
             ApiDetail::Function { fun: _, analysis } => gen_function(ns, analysis),
@@ -344,10 +452,20 @@ impl<'a> RsCodeGenerator<'a> {
                 for_extern_c_ts,
                 is_forward_declaration: _,
                 bindgen_mod_item,
+                bitfield_accessors,
                 analysis,
             } => RsCodegenResult {
                 global_items: Self::generate_extern_type_impl(analysis, &ty_details),
-                impl_entry: None,
+                impl_entry: if bitfield_accessors.is_empty() {
+                    None
+                } else {
+                    Some(Box::new(ImplBlockDetails {
+                        item: ImplItem::Verbatim(quote! {
+                            #(#bitfield_accessors)*
+                        }),
+                        ty: ty_details.final_ident.clone(),
+                    }))
+                },
                 bridge_items: match analysis {
                     TypeKind::ForwardDeclaration => Vec::new(),
                     _ => create_impl_items(&ty_details.final_ident),
@@ -355,6 +473,43 @@ impl<'a> RsCodeGenerator<'a> {
                 extern_c_mod_item: Some(ForeignItem::Verbatim(for_extern_c_ts)),
                 bindgen_mod_item,
             },
+            ApiDetail::ExternCppType {
+                rust_path,
+                analysis: _,
+            } => {
+                // The bindings which own this type already implement
+                // cxx::ExternType for it, as well as any UniquePtr etc.
+                // support, so all we need is a reference to it.
+                let namespace_attr = if ns.is_empty() {
+                    quote! {}
+                } else {
+                    let ns_string = ns.iter().cloned().collect::<Vec<String>>().join("::");
+                    quote! {
+                        #[namespace = #ns_string]
+                    }
+                };
+                RsCodegenResult {
+                    global_items: Vec::new(),
+                    impl_entry: None,
+                    bridge_items: Vec::new(),
+                    extern_c_mod_item: Some(ForeignItem::Verbatim(quote! {
+                        #namespace_attr
+                        type #id = #rust_path;
+                    })),
+                    bindgen_mod_item: Some(Item::Use(parse_quote! {
+                        pub use cxxbridge::#id;
+                    })),
+                }
+            }
+            ApiDetail::BitfieldUnit { items } => RsCodegenResult {
+                global_items: Vec::new(),
+                impl_entry: None,
+                bridge_items: Vec::new(),
+                extern_c_mod_item: None,
+                bindgen_mod_item: Some(Item::Verbatim(quote! {
+                    #(#items)*
+                })),
+            },
             ApiDetail::CType { .. } => RsCodegenResult {
                 global_items: Vec::new(),
                 impl_entry: None,
@@ -376,6 +531,83 @@ impl<'a> RsCodeGenerator<'a> {
         }
     }
 
+    /// A newtype around a primitive, which cxx can hold in containers
+    /// in ways it can't hold the primitive itself.
+    fn generate_primitive_wrapper(ty_details: &TypeApiDetails, inner: Type) -> RsCodegenResult {
+        let id = &ty_details.final_ident;
+        RsCodegenResult {
+            global_items: Self::generate_extern_type_impl(TypeKind::Pod, ty_details),
+            impl_entry: None,
+            bridge_items: create_impl_items(id),
+            extern_c_mod_item: Some(ForeignItem::Verbatim(quote! {
+                type #id = super::bindgen::root::#id;
+            })),
+            bindgen_mod_item: Some(Item::Verbatim(quote! {
+                #[repr(transparent)]
+                #[derive(Debug, Clone, PartialEq)]
+                pub struct #id(pub #inner);
+
+                impl std::ops::Deref for #id {
+                    type Target = #inner;
+                    fn deref(&self) -> &#inner {
+                        &self.0
+                    }
+                }
+
+                impl std::ops::DerefMut for #id {
+                    fn deref_mut(&mut self) -> &mut #inner {
+                        &mut self.0
+                    }
+                }
+
+                impl From<#inner> for #id {
+                    fn from(value: #inner) -> Self {
+                        Self(value)
+                    }
+                }
+
+                impl PartialEq<#inner> for #id {
+                    fn eq(&self, other: &#inner) -> bool {
+                        self.0 == *other
+                    }
+                }
+            })),
+        }
+    }
+
+    /// A type holding a Rust closure, which C++ calls through an
+    /// extern "Rust" method.
+    fn generate_callback(id: &Ident, params: Vec<Type>, ret_type: ReturnType) -> RsCodegenResult {
+        let arg_names: Vec<_> = (0..params.len())
+            .map(|i| make_ident(format!("arg{}", i)))
+            .collect();
+        let bridge_params = params.iter().cloned().map(unqualify_type);
+        let bridge_ret_type = unqualify_ret_type(ret_type.clone());
+        RsCodegenResult {
+            extern_c_mod_item: None,
+            bridge_items: vec![Item::ForeignMod(parse_quote! {
+                extern "Rust" {
+                    type #id;
+                    fn call(self: &#id, #(#arg_names: #bridge_params),*) #bridge_ret_type;
+                }
+            })],
+            global_items: Vec::new(),
+            bindgen_mod_item: Some(Item::Struct(parse_quote! {
+                #[doc(hidden)]
+                pub struct #id(Box<dyn Fn(#(#params),*) #ret_type + Send + Sync>);
+            })),
+            impl_entry: Some(Box::new(ImplBlockDetails {
+                item: parse_quote! {
+                    #[doc(hidden)]
+                    pub fn call(&self, #(#arg_names: #params),*) #ret_type {
+                        (self.0)(#(#arg_names),*)
+                    }
+                },
+                ty: id.clone(),
+            })),
+        }
+    }
+
     fn generate_bindgen_use_stmt(ns: &Namespace, id: &Ident) -> Item {
         let prefix = ["bindgen", "root"].iter().map(make_ident);
         let ns = ns.iter().map(make_ident);
@@ -0,0 +1,49 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{ImplItem, Type};
+
+use crate::{conversion::codegen_cpp::accessor_name, types::make_ident};
+
+use super::unqualify::unqualify_type;
+
+/// Declarations within the `cxx::bridge` of the C++ functions which
+/// give access to the members of a concrete pair type.
+pub(crate) fn pair_accessor_declarations(pair: &Ident, first: &Type, second: &Type) -> TokenStream {
+    let name = |accessor| make_ident(accessor_name(&pair.to_string(), accessor));
+    let (first_fn, second_fn) = (name("first"), name("second"));
+    let first = unqualify_type(first.clone());
+    let second = unqualify_type(second.clone());
+    quote! {
+        fn #first_fn(p: &#pair) -> &#first;
+        fn #second_fn(p: &#pair) -> &#second;
+    }
+}
+
+/// Rust methods on a concrete pair type, built on top of the C++
+/// accessor functions.
+pub(crate) fn pair_accessor_methods(pair: &Ident, first: &Type, second: &Type) -> ImplItem {
+    let name = |accessor| make_ident(accessor_name(&pair.to_string(), accessor));
+    let (first_fn, second_fn) = (name("first"), name("second"));
+    ImplItem::Verbatim(quote! {
+        pub fn first(&self) -> &#first {
+            cxxbridge::#first_fn(self)
+        }
+        pub fn second(&self) -> &#second {
+            cxxbridge::#second_fn(self)
+        }
+    })
+}
@@ -0,0 +1,76 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::quote;
+use syn::{parse_quote, ImplItem, Item, Type};
+
+use crate::{conversion::codegen_cpp::accessor_name, types::make_ident};
+
+use super::unqualify::unqualify_type;
+
+fn getter_name(tuple: &Ident, index: usize) -> Ident {
+    make_ident(accessor_name(&tuple.to_string(), &format!("get{}", index)))
+}
+
+/// Declarations within the `cxx::bridge` of the C++ functions which
+/// give access to the members of a concrete tuple type.
+pub(crate) fn tuple_accessor_declarations(tuple: &Ident, members: &[&Type]) -> TokenStream {
+    let getters = members.iter().enumerate().map(|(i, ty)| {
+        let getter = getter_name(tuple, i);
+        let ty = unqualify_type((*ty).clone());
+        quote! {
+            fn #getter(t: &#tuple) -> &#ty;
+        }
+    });
+    quote! {
+        #(#getters)*
+    }
+}
+
+/// The `get::<N>()` method on a concrete tuple type, which works for
+/// any `N` for which we've implemented `autocxx::TupleElement`.
+pub(crate) fn tuple_accessor_methods() -> ImplItem {
+    ImplItem::Verbatim(quote! {
+        pub fn get<const N: usize>(&self) -> &<Self as autocxx::TupleElement<N>>::Element
+        where
+            Self: autocxx::TupleElement<N>,
+        {
+            <Self as autocxx::TupleElement<N>>::get_element(self)
+        }
+    })
+}
+
+/// Implementations of `autocxx::TupleElement` for each member of a
+/// concrete tuple type, built on top of the C++ accessor functions.
+/// These live within the bindgen mod, so the member types can be
+/// referred to exactly as bindgen did.
+pub(crate) fn tuple_element_impls(tuple: &Ident, members: &[&Type]) -> Vec<Item> {
+    members
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let getter = getter_name(tuple, i);
+            let index = Literal::usize_unsuffixed(i);
+            Item::Impl(parse_quote! {
+                impl autocxx::TupleElement<#index> for #tuple {
+                    type Element = #ty;
+                    fn get_element(&self) -> &#ty {
+                        cxxbridge::#getter(self)
+                    }
+                }
+            })
+        })
+        .collect()
+}
@@ -56,7 +56,7 @@ where
     new_pun
 }
 
-fn unqualify_type(typ: Type) -> Type {
+pub(crate) fn unqualify_type(typ: Type) -> Type {
     match typ {
         Type::Path(typ) => Type::Path(unqualify_type_path(typ)),
         Type::Reference(mut typeref) => {
@@ -67,6 +67,14 @@ fn unqualify_type(typ: Type) -> Type {
             typeptr.elem = unqualify_boxed_type(typeptr.elem);
             Type::Ptr(typeptr)
         }
+        Type::Slice(mut typeslice) => {
+            typeslice.elem = unqualify_boxed_type(typeslice.elem);
+            Type::Slice(typeslice)
+        }
+        Type::Array(mut typearray) => {
+            typearray.elem = unqualify_boxed_type(typearray.elem);
+            Type::Array(typearray)
+        }
         _ => typ,
     }
 }
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use autocxx_parser::{TypeConfig, UnsafePolicy};
+use autocxx_parser::{OverloadNaming, TypeConfig, UnsafePolicy};
 #[allow(unused_imports)]
 use syn::parse_quote;
 use syn::ItemMod;
@@ -31,10 +31,16 @@ use super::BridgeConverter;
 #[allow(dead_code)]
 fn do_test(input: ItemMod) {
     let tc = TypeConfig::new();
-    let bc = BridgeConverter::new(&[], &tc);
+    let bc = BridgeConverter::new(&[], "autocxxgen.h", "cxxgen.h", &tc);
     let inclusions = "".into();
-    bc.convert(input, true, UnsafePolicy::AllFunctionsSafe, inclusions)
-        .unwrap();
+    bc.convert(
+        input,
+        true,
+        UnsafePolicy::AllFunctionsSafe,
+        inclusions,
+        false,
+    )
+    .unwrap();
 }
 
 // How to add a test here
@@ -34,6 +34,8 @@ pub enum ConvertError {
     UnknownType(String),
     OpaqueTypeFound,
     StaticData(String),
+    InvalidSliceParam(String, String),
+    InvalidSystemHeader(String),
 }
 
 impl Display for ConvertError {
@@ -55,6 +57,8 @@ impl Display for ConvertError {
             ConvertError::UnknownType(ty_desc) => write!(f, "Encountered type not yet known by autocxx: {}", ty_desc)?,
             ConvertError::OpaqueTypeFound => write!(f, "Bindgen generated an opaque type (an empty array) somewhere other than a typedef")?,
             ConvertError::StaticData(ty_desc) => write!(f, "Encountered mutable static data, not yet supported: {}", ty_desc)?,
+            ConvertError::InvalidSliceParam(fn_name, problem) => write!(f, "The slice_param! directive for {} could not be applied: {}", fn_name, problem)?,
+            ConvertError::InvalidSystemHeader(name) => write!(f, "The system header <{}> can't be passed to cxx, which needs its path to be valid Rust tokens.", name)?,
         }
         Ok(())
     }
@@ -23,10 +23,11 @@ mod parse;
 mod utilities;
 
 use analysis::fun::FnAnalyzer;
-use autocxx_parser::TypeConfig;
+use autocxx_parser::{OverloadNaming, TypeConfig};
 pub(crate) use codegen_cpp::type_to_cpp::type_to_cpp;
 pub(crate) use codegen_cpp::CppCodeGenerator;
 pub(crate) use codegen_cpp::CppCodegenResults;
+pub(crate) use codegen_cpp::Header;
 pub(crate) use convert_error::ConvertError;
 use syn::{Item, ItemMod};
 
@@ -51,7 +52,9 @@ use self::{
 /// if the bindgen output is not as expected. It may be in future that
 /// we need to be a bit more graceful, but for now, that's OK.
 pub(crate) struct BridgeConverter<'a> {
-    include_list: &'a [String],
+    include_list: &'a [Header],
+    autocxxgen_header_name: &'a str,
+    cxxgen_header_name: &'a str,
     type_config: &'a TypeConfig,
 }
 
@@ -62,9 +65,16 @@ pub(crate) struct CodegenResults {
 }
 
 impl<'a> BridgeConverter<'a> {
-    pub fn new(include_list: &'a [String], type_config: &'a TypeConfig) -> Self {
+    pub fn new(
+        include_list: &'a [Header],
+        autocxxgen_header_name: &'a str,
+        cxxgen_header_name: &'a str,
+        type_config: &'a TypeConfig,
+    ) -> Self {
         Self {
             include_list,
+            autocxxgen_header_name,
+            cxxgen_header_name,
             type_config,
         }
     }
@@ -80,7 +90,9 @@ impl<'a> BridgeConverter<'a> {
         mut bindgen_mod: ItemMod,
         exclude_utilities: bool,
         unsafe_policy: UnsafePolicy,
+        overload_naming: OverloadNaming,
         inclusions: String,
+        has_defines: bool,
     ) -> Result<CodegenResults, ConvertError> {
         match &mut bindgen_mod.content {
             None => Err(ConvertError::NoContent),
@@ -110,6 +122,7 @@ impl<'a> BridgeConverter<'a> {
                 let analyzed_apis = FnAnalyzer::analyze_functions(
                     analyzed_apis,
                     unsafe_policy,
+                    overload_naming,
                     &mut type_converter,
                     self.type_config,
                 )?;
@@ -120,12 +133,20 @@ impl<'a> BridgeConverter<'a> {
                 analysis::ctypes::append_ctype_information(&mut analyzed_apis);
                 // And finally pass them to the code gen phases, which outputs
                 // code suitable for cxx to consume.
-                let cpp = CppCodeGenerator::generate_cpp_code(inclusions, &analyzed_apis)?;
+                let cpp = CppCodeGenerator::generate_cpp_code(
+                    inclusions,
+                    self.autocxxgen_header_name,
+                    self.cxxgen_header_name,
+                    &analyzed_apis,
+                    has_defines,
+                )?;
                 let rs = RsCodeGenerator::generate_rs_code(
                     analyzed_apis,
                     self.include_list,
+                    self.autocxxgen_header_name,
                     bindgen_mod,
-                );
+                    has_defines,
+                )?;
                 Ok(CodegenResults { rs, cpp })
             }
         }
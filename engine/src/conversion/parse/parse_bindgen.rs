@@ -24,9 +24,12 @@ use crate::{
     types::TypeName,
 };
 use autocxx_parser::TypeConfig;
-use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
-use syn::{parse_quote, Fields, Ident, Item, Type, TypePath, UseTree};
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, Fields, GenericArgument, Ident, ImplItem, Item, ItemStruct, ItemUnion,
+    PathArguments, Type, TypePath, UseTree,
+};
 
 use super::{super::utilities::generate_utilities, type_converter::TypeConverter};
 
@@ -98,7 +101,9 @@ impl<'a> ParseBindgen<'a> {
         for item in items {
             let r = self.parse_item(item, &mut mod_converter, &ns);
             match r {
-                Err(err) if err.is_ignorable() => {
+                // With generate_all! the user hasn't chosen any of these
+                // items specifically, so skip whatever we can't convert.
+                Err(err) if err.is_ignorable() || self.type_config.is_generate_all() => {
                     eprintln!("Ignored item discovered whilst parsing: {}", err)
                 }
                 Err(_) => r.unwrap(),
@@ -122,6 +127,15 @@ impl<'a> ParseBindgen<'a> {
                     return Ok(());
                 }
                 let tyname = TypeName::new(ns, &s.ident.to_string());
+                if tyname.is_bitfield_unit() {
+                    self.add_bitfield_unit_item(Item::Struct(s));
+                    return Ok(());
+                }
+                if tyname.is_union_field() {
+                    // We turn unions back into real Rust unions, so
+                    // never need this.
+                    return Ok(());
+                }
                 let is_forward_declaration = Self::spot_forward_declaration(&s.fields);
                 // cxx::bridge can't cope with type aliases to generic
                 // types at the moment.
@@ -129,21 +143,56 @@ impl<'a> ParseBindgen<'a> {
                     tyname.clone(),
                     is_forward_declaration,
                     HashSet::new(),
-                    Some(Item::Struct(s)),
+                    Some(Self::convert_emulated_union(s)),
                 );
                 self.latest_virtual_this_type = Some(tyname);
                 Ok(())
             }
+            Item::Union(u) => {
+                let tyname = TypeName::new(ns, &u.ident.to_string());
+                self.parse_type(tyname.clone(), false, HashSet::new(), Some(Item::Union(u)));
+                self.latest_virtual_this_type = Some(tyname);
+                Ok(())
+            }
             Item::Enum(e) => {
                 let tyname = TypeName::new(ns, &e.ident.to_string());
                 self.parse_type(tyname, false, HashSet::new(), Some(Item::Enum(e)));
                 Ok(())
             }
             Item::Impl(imp) => {
+                let tyname = match imp.self_ty.as_ref() {
+                    Type::Path(typ) => Some(TypeName::new(
+                        ns,
+                        &typ.path.segments.last().unwrap().ident.to_string(),
+                    )),
+                    _ => None,
+                };
+                if tyname
+                    .as_ref()
+                    .map(TypeName::is_bitfield_unit)
+                    .unwrap_or(false)
+                {
+                    self.add_bitfield_unit_item(Item::Impl(imp));
+                    return Ok(());
+                }
+                if tyname
+                    .as_ref()
+                    .map(TypeName::is_union_field)
+                    .unwrap_or(false)
+                {
+                    return Ok(());
+                }
                 // We *mostly* ignore all impl blocks generated by bindgen.
                 // Methods also appear in 'extern "C"' blocks which
                 // we will convert instead. At that time we'll also construct
                 // synthetic impl blocks.
+                // The exception is bitfield accessors, which are pure Rust.
+                if imp.trait_.is_none() {
+                    if let Some(tyname) = tyname {
+                        let bitfield_accessors = Self::find_bitfield_accessors(&imp.items);
+                        self.add_bitfield_accessors(&tyname, bitfield_accessors.into_iter());
+                    }
+                }
                 // We do however record which methods were spotted, since
                 // we have no other way of working out which functions are
                 // static methods vs plain functions.
@@ -252,6 +301,152 @@ impl<'a> ParseBindgen<'a> {
             .any(|id| id == "_unused")
     }
 
+    /// Record part of the storage type which bindgen generates for
+    /// bitfields. We pass it through unchanged, so that structs containing
+    /// bitfields can be POD.
+    fn add_bitfield_unit_item(&mut self, item: Item) {
+        let existing = self
+            .results
+            .apis
+            .iter_mut()
+            .find_map(|api| match &mut api.detail {
+                ApiDetail::BitfieldUnit { items } => Some(items),
+                _ => None,
+            });
+        match existing {
+            Some(items) => items.push(item),
+            None => self.results.apis.push(UnanalyzedApi {
+                id: make_ident("__BindgenBitfieldUnit"),
+                ns: Namespace::new(),
+                deps: HashSet::new(),
+                detail: ApiDetail::BitfieldUnit { items: vec![item] },
+            }),
+        }
+    }
+
+    /// bindgen generates a getter and a `set_` setter for each bitfield,
+    /// each of which reads or writes one of the struct's `_bitfield_N`
+    /// storage fields. Pick out just those pairs, and not, for instance,
+    /// the `new_bitfield_N` functions which construct the storage.
+    fn find_bitfield_accessors(items: &[ImplItem]) -> Vec<ImplItem> {
+        let accessor_names: HashSet<String> = items
+            .iter()
+            .filter_map(|item| match item {
+                ImplItem::Method(m)
+                    if m.sig.unsafety.is_none()
+                        && Self::uses_bitfield_storage(m.block.to_token_stream()) =>
+                {
+                    Some(m.sig.ident.to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        let has_partner = |name: &str| match name.strip_prefix("set_") {
+            Some(getter) if accessor_names.contains(getter) => true,
+            _ => accessor_names.contains(&format!("set_{}", name)),
+        };
+        items
+            .iter()
+            .filter(|item| match item {
+                ImplItem::Method(m) => {
+                    let name = m.sig.ident.to_string();
+                    accessor_names.contains(&name) && has_partner(&name)
+                }
+                _ => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Whether these tokens refer to `self._bitfield_N`.
+    fn uses_bitfield_storage(tokens: TokenStream2) -> bool {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        tokens.windows(3).any(|window| match window {
+            [TokenTree::Ident(this), TokenTree::Punct(dot), TokenTree::Ident(field)] => {
+                this == "self"
+                    && dot.as_char() == '.'
+                    && field.to_string().starts_with("_bitfield_")
+            }
+            _ => false,
+        }) || tokens.into_iter().any(|token| match token {
+            TokenTree::Group(group) => Self::uses_bitfield_storage(group.stream()),
+            _ => false,
+        })
+    }
+
+    /// Bitfield accessors always immediately follow the struct to which
+    /// they belong.
+    fn add_bitfield_accessors(
+        &mut self,
+        tyname: &TypeName,
+        accessors: impl Iterator<Item = ImplItem>,
+    ) {
+        if let Some(api) = self.results.apis.last_mut() {
+            if api.typename() == *tyname {
+                // Accessors for bitfields within unions rely on bindgen's
+                // representation of the union, which we don't keep.
+                if let ApiDetail::Type {
+                    bindgen_mod_item: Some(Item::Struct(_)),
+                    bitfield_accessors,
+                    ..
+                } = &mut api.detail
+                {
+                    bitfield_accessors.extend(accessors);
+                }
+            }
+        }
+    }
+
+    /// Unless it can derive `Copy`, which we never ask it to, bindgen
+    /// represents a C++ union as a struct holding the union's storage
+    /// and a zero-sized marker for each member. Turn that back into a
+    /// Rust union. bindgen's storage field stays as an extra member, so
+    /// that the size and alignment are exactly as bindgen calculated.
+    fn convert_emulated_union(s: ItemStruct) -> Item {
+        let is_union = s
+            .fields
+            .iter()
+            .filter_map(|f| f.ident.as_ref())
+            .any(|id| id == "bindgen_union_field");
+        let mut fields = match s.fields {
+            Fields::Named(fields) if is_union => fields,
+            _ => return Item::Struct(s),
+        };
+        for f in fields.named.iter_mut() {
+            if let Some(ty) = Self::get_union_field_type(&f.ty) {
+                f.ty = ty;
+            }
+        }
+        Item::Union(ItemUnion {
+            // Unions can't derive most of what bindgen might ask for.
+            attrs: s
+                .attrs
+                .into_iter()
+                .filter(|attr| !attr.path.is_ident("derive"))
+                .collect(),
+            vis: s.vis,
+            union_token: Default::default(),
+            ident: s.ident,
+            generics: s.generics,
+            fields,
+        })
+    }
+
+    /// The member type within a `__BindgenUnionField<T>`.
+    fn get_union_field_type(ty: &Type) -> Option<Type> {
+        let typ = match ty {
+            Type::Path(typ) if TypeName::from_type_path(typ).is_union_field() => typ,
+            _ => return None,
+        };
+        match &typ.path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(ab) => match ab.args.first() {
+                Some(GenericArgument::Type(ty)) => Some(ty.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     fn add_opaque_type(&mut self, id: Ident, ns: Namespace) {
         self.results.apis.push(UnanalyzedApi {
             id,
@@ -279,6 +474,21 @@ impl<'a> ParseBindgen<'a> {
             return;
         }
         let tynamestring = tyname.to_cpp_name();
+        if let Some(rust_path) = self.type_config.get_extern_cpp_type(&tynamestring) {
+            // Bindings for this type already exist elsewhere, so we neither
+            // need nor want anything which bindgen told us about its contents.
+            self.results.apis.push(UnanalyzedApi {
+                ns: tyname.get_namespace().clone(),
+                id: final_ident,
+                deps: HashSet::new(),
+                detail: ApiDetail::ExternCppType {
+                    rust_path: rust_path.clone(),
+                    analysis: (),
+                },
+            });
+            self.results.type_converter.push(tyname);
+            return;
+        }
         let mut for_extern_c_ts = if tyname.has_namespace() {
             let ns_string = tyname
                 .ns_segment_iter()
@@ -320,6 +530,7 @@ impl<'a> ParseBindgen<'a> {
                 for_extern_c_ts,
                 is_forward_declaration,
                 bindgen_mod_item,
+                bitfield_accessors: Vec::new(),
                 analysis: (),
             },
         };
@@ -80,6 +80,8 @@ pub(crate) struct TypeConverter {
     types_found: Vec<TypeName>,
     typedefs: HashMap<TypeName, Type>,
     concrete_templates: HashMap<String, TypeName>,
+    concrete_definitions: HashMap<TypeName, Type>,
+    primitive_wrappers: HashSet<TypeName>,
 }
 
 impl TypeConverter {
@@ -88,6 +90,8 @@ impl TypeConverter {
             types_found: Vec::new(),
             typedefs: HashMap::new(),
             concrete_templates: HashMap::new(),
+            concrete_definitions: HashMap::new(),
+            primitive_wrappers: HashSet::new(),
         }
     }
 
@@ -166,6 +170,16 @@ impl TypeConverter {
                     true,
                 )
             }
+            Type::Array(mut arr) => {
+                let innerty = self.convert_boxed_type(arr.elem, ns, false)?;
+                arr.elem = innerty.ty;
+                Annotated::new(
+                    Type::Array(arr),
+                    innerty.types_encountered,
+                    innerty.extra_apis,
+                    false,
+                )
+            }
             _ => Annotated::new(ty, HashSet::new(), Vec::new(), false),
         };
         Ok(result)
@@ -256,8 +270,24 @@ impl TypeConverter {
         if let Some(last_seg_args) = last_seg_args {
             let last_seg = typ.path.segments.last_mut().unwrap();
             last_seg.arguments = last_seg_args;
-            // Is it one of the things built into cxx?
-            if !KNOWN_TYPES.is_cxx_acceptable_generic(&tn) {
+            if KNOWN_TYPES.is_cxx_acceptable_generic(&tn) {
+                self.wrap_primitive_args(
+                    &tn,
+                    &mut last_seg.arguments,
+                    &mut types_encountered,
+                    &mut extra_apis,
+                );
+            }
+            // Is it one of the things built into cxx, or std::optional,
+            // std::function or std::span which we'll convert in wrapper
+            // functions? Or bindgen's bitfield storage, which is only ever
+            // a field of a struct and never seen by C++?
+            if !KNOWN_TYPES.is_cxx_acceptable_generic(&tn)
+                && !KNOWN_TYPES.is_optional(&tn)
+                && !KNOWN_TYPES.is_function(&tn)
+                && !KNOWN_TYPES.is_span(&tn)
+                && !tn.is_bitfield_unit()
+            {
                 // Oh poop. It's a generic type which cxx won't be able to handle.
                 // We'll have to come up with a concrete type in both the cxx::bridge (in Rust)
                 // and a corresponding typedef in C++.
@@ -276,6 +306,76 @@ impl TypeConverter {
         ))
     }
 
+    /// Replaces any primitive type arguments of a cxx container which
+    /// cxx itself can't cope with (e.g. the `u32` in `UniquePtr<u32>`)
+    /// with a newtype wrapper which it can.
+    fn wrap_primitive_args(
+        &mut self,
+        container: &TypeName,
+        args: &mut PathArguments,
+        types_encountered: &mut HashSet<TypeName>,
+        extra_apis: &mut Vec<UnanalyzedApi>,
+    ) {
+        if let PathArguments::AngleBracketed(ab) = args {
+            for arg in ab.args.iter_mut() {
+                if let GenericArgument::Type(Type::Path(inner)) = arg {
+                    let inner_tn = TypeName::from_type_path(inner);
+                    if KNOWN_TYPES.needs_primitive_wrapper(container, &inner_tn) {
+                        let wrapper_tn = Self::get_primitive_wrapper_name(&inner_tn);
+                        if self.primitive_wrappers.insert(wrapper_tn.clone()) {
+                            extra_apis.push(Self::add_primitive_wrapper(
+                                &wrapper_tn,
+                                &Type::Path(inner.clone()),
+                            ));
+                        }
+                        *inner = wrapper_tn.to_type_path();
+                        types_encountered.remove(&inner_tn);
+                        types_encountered.insert(wrapper_tn);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The name of the wrapper for a given primitive, e.g.
+    /// `AutocxxPrimitiveCInt` for `autocxx::c_int`.
+    fn get_primitive_wrapper_name(inner: &TypeName) -> TypeName {
+        let camel_case: String = inner
+            .get_final_ident()
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect();
+        TypeName::new(
+            &Namespace::new(),
+            &format!("AutocxxPrimitive{}", camel_case),
+        )
+    }
+
+    fn add_primitive_wrapper(tyname: &TypeName, inner: &Type) -> UnanalyzedApi {
+        let final_ident = make_ident(tyname.get_final_ident());
+        let mut fulltypath: Vec<_> = ["bindgen", "root"].iter().map(make_ident).collect();
+        fulltypath.push(final_ident.clone());
+        UnanalyzedApi {
+            ns: tyname.get_namespace().clone(),
+            id: final_ident.clone(),
+            deps: HashSet::new(),
+            detail: crate::conversion::api::ApiDetail::PrimitiveWrapper {
+                ty_details: TypeApiDetails {
+                    fulltypath,
+                    tynamestring: tyname.to_cpp_name(),
+                    final_ident,
+                },
+                inner: inner.clone(),
+            },
+        }
+    }
+
     fn get_last_segment_args(typ: &TypePath) -> Option<PathArguments> {
         let mut seg_iter = typ.path.segments.iter().peekable();
         while let Some(seg) = seg_iter.next() {
@@ -341,16 +441,25 @@ impl TypeConverter {
         // a wobbler if not. rust::Str should only be seen _by value_ in C++
         // headers; it manifests as &str in Rust but on the C++ side it must
         // be a plain value. We should detect and abort.
-        Ok(elem.map(|elem| match mutability {
-            Some(_) => Type::Path(parse_quote! {
+        Ok(elem.map(|elem| match (mutability, elem.as_ref()) {
+            // Arrays are Unpin, so there's no need for Pin.
+            (Some(_), Type::Array(_)) => Type::Reference(parse_quote! {
+                & #mutability #elem
+            }),
+            (Some(_), _) => Type::Path(parse_quote! {
                 std::pin::Pin < & #mutability #elem >
             }),
-            None => Type::Reference(parse_quote! {
+            (None, _) => Type::Reference(parse_quote! {
                 & #elem
             }),
         }))
     }
 
+    /// The templated type which a concrete type was instantiated from.
+    pub(crate) fn get_concrete_definition(&self, tn: &TypeName) -> Option<&Type> {
+        self.concrete_definitions.get(tn)
+    }
+
     fn add_concrete_type(&self, tyname: &TypeName, rs_definition: &Type) -> UnanalyzedApi {
         let final_ident = make_ident(tyname.get_final_ident());
         let mut fulltypath: Vec<_> = ["bindgen", "root"].iter().map(make_ident).collect();
@@ -388,6 +497,8 @@ impl TypeConverter {
                 let tn = TypeName::new(&Namespace::new(), &format!("AutocxxConcrete{}", count));
                 self.concrete_templates
                     .insert(cpp_definition.clone(), tn.clone());
+                self.concrete_definitions
+                    .insert(tn.clone(), rs_definition.clone());
                 let api = self.add_concrete_type(&tn, rs_definition);
                 Ok((tn, Some(api)))
             }
@@ -114,6 +114,7 @@ fn run_test(
         generate,
         generate_pods,
         None,
+        None,
     )
     .unwrap()
 }
@@ -134,6 +135,30 @@ fn run_test_ex(
         generate,
         generate_pods,
         extra_directives,
+        None,
+    )
+    .unwrap()
+}
+
+/// A positive test, we expect to pass, with some extra Rust items
+/// alongside the `include_cpp!`.
+fn run_test_with_extra_rust(
+    cxx_code: &str,
+    header_code: &str,
+    rust_code: TokenStream,
+    generate: &[&str],
+    generate_pods: &[&str],
+    extra_directives: Option<TokenStream>,
+    extra_rust: TokenStream,
+) {
+    do_run_test(
+        cxx_code,
+        header_code,
+        rust_code,
+        generate,
+        generate_pods,
+        extra_directives,
+        Some(extra_rust),
     )
     .unwrap()
 }
@@ -152,6 +177,7 @@ fn run_test_expect_fail(
         generate,
         generate_pods,
         None,
+        None,
     )
     .expect_err("Unexpected success");
 }
@@ -171,6 +197,7 @@ fn do_run_test(
     generate: &[&str],
     generate_pods: &[&str],
     extra_directives: Option<TokenStream>,
+    extra_rust: Option<TokenStream>,
 ) -> Result<(), TestError> {
     // Step 1: Write the C++ header snippet to a temp file
     let tdir = tempdir().unwrap();
@@ -202,6 +229,8 @@ fn do_run_test(
             #extra_directives
         );
 
+        #extra_rust
+
         fn main() {
             #rust_code
         }
@@ -226,6 +255,7 @@ fn do_run_test(
     let build_results = crate::builder::build_to_custom_directory(
         &rs_path,
         &[tdir.path()],
+        &[],
         Some(target_dir.clone()),
         None,
     )
@@ -247,7 +277,6 @@ fn do_run_test(
         .host(&target)
         .target(&target)
         .opt_level(1)
-        .flag("-std=c++14")
         .include(tdir.path())
         .try_compile("autocxx-demo")
         .map_err(TestError::CppBuild)?;
@@ -365,7 +394,6 @@ fn test_take_i32() {
 }
 
 #[test]
-#[ignore] // because cxx doesn't support unique_ptrs to primitives.
 fn test_give_up_int() {
     let cxx = indoc! {"
         std::unique_ptr<uint32_t> give_up() {
@@ -378,13 +406,12 @@ fn test_give_up_int() {
         std::unique_ptr<uint32_t> give_up();
     "};
     let rs = quote! {
-        assert_eq!(ffi::give_up().as_ref().unwrap(), 12);
+        assert_eq!(*ffi::give_up().as_ref().unwrap(), 12);
     };
     run_test(cxx, hdr, rs, &["give_up"], &[]);
 }
 
 #[test]
-#[ignore] // because we don't yet implement UniquePtr etc. for autocxx::c_int and friends
 fn test_give_up_ctype() {
     let cxx = indoc! {"
         std::unique_ptr<int> give_up() {
@@ -396,11 +423,48 @@ fn test_give_up_ctype() {
         std::unique_ptr<int> give_up();
     "};
     let rs = quote! {
-        assert_eq!(ffi::give_up().as_ref().unwrap(), autocxx::c_int(12));
+        assert_eq!(*ffi::give_up().as_ref().unwrap(), autocxx::c_int(12));
     };
     run_test(cxx, hdr, rs, &["give_up"], &[]);
 }
 
+#[test]
+fn test_primitive_containers() {
+    let cxx = indoc! {"
+        std::unique_ptr<long> give_up_long() {
+            return std::make_unique<long>(12);
+        }
+        std::unique_ptr<std::vector<int>> give_up_ints() {
+            auto ints = std::make_unique<std::vector<int>>();
+            ints->push_back(3);
+            ints->push_back(4);
+            return ints;
+        }
+        int sum(const std::vector<int>& ints) {
+            int total = 0;
+            for (auto i: ints) {
+                total += i;
+            }
+            return total;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <memory>
+        #include <vector>
+        std::unique_ptr<long> give_up_long();
+        std::unique_ptr<std::vector<int>> give_up_ints();
+        int sum(const std::vector<int>& ints);
+    "};
+    let rs = quote! {
+        assert_eq!(*ffi::give_up_long().as_ref().unwrap(), autocxx::c_long(12));
+        let ints = ffi::give_up_ints();
+        assert_eq!(ints.len(), 2);
+        assert_eq!(**ints.get(1).unwrap(), autocxx::c_int(4));
+        assert_eq!(ffi::sum(ints.as_ref().unwrap()), autocxx::c_int(7));
+    };
+    run_test(cxx, hdr, rs, &["give_up_long", "give_up_ints", "sum"], &[]);
+}
+
 #[test]
 fn test_give_string_up() {
     let cxx = indoc! {"
@@ -1130,7 +1194,7 @@ fn test_make_up_with_args() {
 }
 
 #[test]
-#[ignore] // because we don't support unique_ptrs to primitives
+#[ignore] // because the fields of non-POD types aren't accessible
 fn test_make_up_int() {
     let cxx = indoc! {"
         Bob::Bob(uint32_t a) : b(a) {
@@ -1235,7 +1299,7 @@ fn test_pod_method() {
         let a = ffi::Bob { a: 12, b: 13 };
         assert_eq!(a.get_bob(), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob"], &["Bob"]);
+    run_test(cxx, hdr, rs, &[], &["Bob"]);
 }
 
 #[test]
@@ -1258,7 +1322,7 @@ fn test_pod_mut_method() {
         let mut a = Box::pin(ffi::Bob { a: 12, b: 13 });
         assert_eq!(a.as_mut().get_bob(), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob"], &["Bob"]);
+    run_test(cxx, hdr, rs, &[], &["Bob"]);
 }
 
 #[test]
@@ -1390,7 +1454,7 @@ fn test_method_pass_pod_by_value() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob"], &["Bob", "Anna"]);
+    run_test(cxx, hdr, rs, &[], &["Bob", "Anna"]);
 }
 
 #[test]
@@ -1414,7 +1478,7 @@ fn test_inline_method() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a), 12);
     };
-    run_test("", hdr, rs, &["take_bob"], &["Bob", "Anna"]);
+    run_test("", hdr, rs, &[], &["Bob", "Anna"]);
 }
 
 #[test]
@@ -1441,7 +1505,7 @@ fn test_method_pass_pod_by_reference() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(&a), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob"], &["Bob", "Anna"]);
+    run_test(cxx, hdr, rs, &[], &["Bob", "Anna"]);
 }
 
 #[test]
@@ -1468,7 +1532,7 @@ fn test_method_pass_pod_by_mut_reference() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a.as_mut()), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob"], &["Bob", "Anna"]);
+    run_test(cxx, hdr, rs, &[], &["Bob", "Anna"]);
 }
 
 #[test]
@@ -1496,7 +1560,7 @@ fn test_method_pass_pod_by_up() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(cxx::UniquePtr::new(a)), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob"], &["Bob", "Anna"]);
+    run_test(cxx, hdr, rs, &[], &["Bob", "Anna"]);
 }
 
 #[test]
@@ -1531,13 +1595,7 @@ fn test_method_pass_nonpod_by_value() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a), 12);
     };
-    run_test(
-        cxx,
-        hdr,
-        rs,
-        &["take_bob", "Anna", "give_anna", "get_bob"],
-        &["Bob"],
-    );
+    run_test(cxx, hdr, rs, &["Anna", "give_anna", "get_bob"], &["Bob"]);
 }
 
 #[test]
@@ -1576,13 +1634,7 @@ fn test_method_pass_nonpod_by_value_with_up() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a, a2), 12);
     };
-    run_test(
-        cxx,
-        hdr,
-        rs,
-        &["take_bob", "Anna", "give_anna", "get_bob"],
-        &["Bob"],
-    );
+    run_test(cxx, hdr, rs, &["Anna", "give_anna", "get_bob"], &["Bob"]);
 }
 
 #[test]
@@ -1617,7 +1669,7 @@ fn test_method_pass_nonpod_by_reference() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a.as_ref().unwrap()), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob", "Anna", "give_anna"], &["Bob"]);
+    run_test(cxx, hdr, rs, &["Anna", "give_anna"], &["Bob"]);
 }
 
 #[test]
@@ -1652,7 +1704,7 @@ fn test_method_pass_nonpod_by_mut_reference() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a.as_mut().unwrap()), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob", "Anna", "give_anna"], &["Bob"]);
+    run_test(cxx, hdr, rs, &["Anna", "give_anna"], &["Bob"]);
 }
 
 #[test]
@@ -1688,7 +1740,7 @@ fn test_method_pass_nonpod_by_up() {
         let b = ffi::Bob { a: 12, b: 13 };
         assert_eq!(b.get_bob(a), 12);
     };
-    run_test(cxx, hdr, rs, &["take_bob", "give_anna"], &["Bob"]);
+    run_test(cxx, hdr, rs, &["give_anna"], &["Bob"]);
 }
 
 #[test]
@@ -1719,7 +1771,7 @@ fn test_method_return_nonpod_by_value() {
         let a = b.get_anna();
         assert!(!a.is_null());
     };
-    run_test(cxx, hdr, rs, &["take_bob", "Anna", "get_anna"], &["Bob"]);
+    run_test(cxx, hdr, rs, &["Anna", "get_anna"], &["Bob"]);
 }
 
 #[test]
@@ -1824,7 +1876,7 @@ fn test_method_return_string_by_value() {
         let a = b.get_msg();
         assert!(a.as_ref().unwrap() == "hello");
     };
-    run_test(cxx, hdr, rs, &["take_bob", "get_msg"], &["Bob"]);
+    run_test(cxx, hdr, rs, &["get_msg"], &["Bob"]);
 }
 
 #[test]
@@ -3555,11 +3607,42 @@ fn test_ulong() {
     run_test("", hdr, rs, &["daft"], &[]);
 }
 
+#[test]
+fn test_other_ctypes() {
+    let hdr = indoc! {"
+    inline long long daft_longlong(long long a) { return a; }
+    inline unsigned long long daft_ulonglong(unsigned long long a) { return a; }
+    inline signed char daft_schar(signed char a) { return a; }
+    inline unsigned char daft_uchar(unsigned char a) { return a; }
+    inline unsigned short daft_ushort(unsigned short a) { return a; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::daft_longlong(autocxx::c_longlong(-34)), autocxx::c_longlong(-34));
+        assert_eq!(ffi::daft_ulonglong(autocxx::c_ulonglong(34)), autocxx::c_ulonglong(34));
+        assert_eq!(ffi::daft_schar(autocxx::c_schar(-3)), autocxx::c_schar(-3));
+        assert_eq!(ffi::daft_uchar(autocxx::c_uchar(3)), autocxx::c_uchar(3));
+        assert_eq!(ffi::daft_ushort(autocxx::c_ushort(34)), autocxx::c_ushort(34));
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &[
+            "daft_longlong",
+            "daft_ulonglong",
+            "daft_schar",
+            "daft_uchar",
+            "daft_ushort",
+        ],
+        &[],
+    );
+}
+
 #[test]
 fn test_typedef_to_ulong() {
     let hdr = indoc! {"
-        #include <cstddef>
-        inline size_t daft(size_t a) { return a; }
+        typedef unsigned long my_ulong_t;
+        inline my_ulong_t daft(my_ulong_t a) { return a; }
     "};
     let rs = quote! {
         assert_eq!(ffi::daft(autocxx::c_ulong(34)), autocxx::c_ulong(34));
@@ -3567,6 +3650,48 @@ fn test_typedef_to_ulong() {
     run_test("", hdr, rs, &["daft"], &[]);
 }
 
+#[test]
+fn test_size_t() {
+    let hdr = indoc! {"
+        #include <cstddef>
+        #include <cstdint>
+        #include <sys/types.h>
+        inline size_t daft_size_t(size_t a) { return a; }
+        inline ssize_t daft_ssize_t(ssize_t a) { return a; }
+        inline ptrdiff_t daft_ptrdiff_t(ptrdiff_t a) { return a; }
+        inline intptr_t daft_intptr_t(intptr_t a) { return a; }
+        inline uintptr_t daft_uintptr_t(uintptr_t a) { return a; }
+        struct A {
+            size_t len;
+            ptrdiff_t offset;
+        };
+        inline size_t get_len(A a) { return a.len; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::daft_size_t(34usize), 34usize);
+        assert_eq!(ffi::daft_ssize_t(-34isize), -34isize);
+        assert_eq!(ffi::daft_ptrdiff_t(-34isize), -34isize);
+        assert_eq!(ffi::daft_intptr_t(-34isize), -34isize);
+        assert_eq!(ffi::daft_uintptr_t(34usize), 34usize);
+        let a = ffi::A { len: 12usize, offset: -3isize };
+        assert_eq!(ffi::get_len(a), 12usize);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &[
+            "daft_size_t",
+            "daft_ssize_t",
+            "daft_ptrdiff_t",
+            "daft_intptr_t",
+            "daft_uintptr_t",
+            "get_len",
+        ],
+        &["A"],
+    );
+}
+
 #[test]
 fn test_generate_typedef_to_ulong() {
     let hdr = indoc! {"
@@ -4039,7 +4164,6 @@ fn test_simple_dependent_qualified_type() {
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/266
 fn test_take_array() {
     let hdr = indoc! {"
     #include <cstdint>
@@ -4049,18 +4173,1228 @@ fn test_take_array() {
     "};
     let rs = quote! {
         let c: [u32; 4usize] = [ 10, 20, 30, 40 ];
-        let c = c as *const [_];
         assert_eq!(ffi::take_array(&c), 40);
     };
     run_test("", hdr, rs, &["take_array"], &[]);
 }
 
+#[test]
+fn test_take_mut_array() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    inline void double_array(uint32_t a[4]) {
+        for (int i = 0; i < 4; i++) {
+            a[i] *= 2;
+        }
+    }
+    "};
+    let rs = quote! {
+        let mut c: [u32; 4usize] = [ 10, 20, 30, 40 ];
+        ffi::double_array(&mut c);
+        assert_eq!(c, [20, 40, 60, 80]);
+    };
+    run_test("", hdr, rs, &["double_array"], &[]);
+}
+
+#[test]
+fn test_take_array_reference() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    inline uint32_t take_array_ref(const uint32_t (&a)[4]) {
+        return a[1] + a[3];
+    }
+    "};
+    let rs = quote! {
+        let c: [u32; 4usize] = [ 10, 20, 30, 40 ];
+        assert_eq!(ffi::take_array_ref(&c), 60);
+    };
+    run_test("", hdr, rs, &["take_array_ref"], &[]);
+}
+
+#[test]
+fn test_pod_with_array() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct Histogram {
+        uint32_t data[16];
+        uint32_t total;
+    };
+    inline Histogram make_histogram() {
+        Histogram h;
+        for (int i = 0; i < 16; i++) {
+            h.data[i] = i;
+        }
+        h.total = 120;
+        return h;
+    }
+    "};
+    let rs = quote! {
+        let h = ffi::make_histogram();
+        assert_eq!(h.data[3], 3);
+        assert_eq!(h.data.iter().sum::<u32>(), h.total);
+    };
+    run_test("", hdr, rs, &["make_histogram"], &["Histogram"]);
+}
+
+#[test]
+fn test_generate_pattern() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    namespace net {
+        inline uint32_t get_port() { return 80; }
+        inline uint32_t get_timeout() { return 30; }
+    }
+    inline uint32_t get_other() { return 5; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::net::get_port(), 80);
+        assert_eq!(ffi::net::get_timeout(), 30);
+    };
+    run_test("", hdr, rs, &["net::get_.*"], &[]);
+}
+
+#[test]
+fn test_generate_pod_pattern() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct Point2D {
+        uint32_t x;
+        uint32_t y;
+    };
+    struct Point3D {
+        uint32_t x;
+        uint32_t y;
+        uint32_t z;
+    };
+    inline uint32_t sum2(Point2D p) { return p.x + p.y; }
+    inline uint32_t sum3(Point3D p) { return p.x + p.y + p.z; }
+    "};
+    let rs = quote! {
+        let a = ffi::Point2D { x: 1, y: 2 };
+        let b = ffi::Point3D { x: 1, y: 2, z: 3 };
+        assert_eq!(ffi::sum2(a), 3);
+        assert_eq!(ffi::sum3(b), 6);
+    };
+    run_test("", hdr, rs, &["sum2", "sum3"], &["Point.D"]);
+}
+
+#[test]
+fn test_block_pattern() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct Bob {
+        uint32_t a;
+    };
+    struct BadThing {
+        uint32_t a;
+    };
+    inline uint32_t take_bob(const Bob& b) { return b.a; }
+    inline uint32_t take_bad(const BadThing& b) { return b.a; }
+    "};
+    let rs = quote! {
+        let b = ffi::Bob { a: 12 };
+        assert_eq!(ffi::take_bob(&b), 12);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["take_.*"],
+        &["Bob"],
+        Some(quote! { block!("Bad.*") }),
+    );
+}
+
+#[test]
+fn test_generate_ns() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    namespace geometry {
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+        inline Point make_point() { Point p; p.x = 2; p.y = 3; return p; }
+        inline uint32_t manhattan(const Point& p) { return p.x + p.y; }
+        namespace detail {
+            inline uint32_t origin_x() { return 0; }
+        }
+    }
+    inline uint32_t not_in_ns() { return 3; }
+    "};
+    let rs = quote! {
+        let p = ffi::geometry::make_point();
+        assert_eq!(ffi::geometry::manhattan(&p), 5);
+        assert_eq!(ffi::geometry::detail::origin_x(), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &[],
+        &[],
+        Some(quote! { generate_ns!("geometry") }),
+    );
+}
+
+#[test]
+fn test_define() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    #ifdef MYLIB_ENABLE_FOO
+    inline uint32_t foo() { return MYLIB_FOO_VALUE; }
+    #endif
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::foo(), 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["foo"],
+        &[],
+        Some(quote! {
+            define!("MYLIB_ENABLE_FOO")
+            define!("MYLIB_FOO_VALUE", "7")
+        }),
+    );
+}
+
+#[test]
+fn test_cpp17() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    inline constexpr uint32_t ANSWER = 42;
+    inline uint32_t get_answer() {
+        if constexpr (ANSWER > 40) {
+            return ANSWER;
+        } else {
+            return 0;
+        }
+    }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_answer(), 42);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["get_answer"],
+        &[],
+        Some(quote! { cpp_standard!("c++17") }),
+    );
+}
+
+#[test]
+fn test_conflicting_cpp_standards() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    inline uint32_t give_int() { return 5; }
+    "};
+    let hexathorpe = Token![#](Span::call_site());
+    let err = do_run_test(
+        "",
+        hdr,
+        quote! {},
+        &["give_int"],
+        &[],
+        None,
+        Some(quote! {
+            include_cpp!(
+                #hexathorpe include "input.h"
+                safety!(unsafe_ffi)
+                generate!("give_int")
+                cpp_standard!("c++17")
+                name!(ffi17)
+            );
+        }),
+    )
+    .expect_err("Unexpected success");
+    assert!(matches!(
+        err,
+        TestError::AutoCxx(crate::BuilderError::ConflictingCppStandards(..))
+    ));
+}
+
+#[test]
+fn test_rename_function() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    inline uint32_t GetFooBarV2() { return 4; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::foo_bar(), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["GetFooBarV2"],
+        &[],
+        Some(quote! { rename!("GetFooBarV2", "foo_bar") }),
+    );
+}
+
+#[test]
+fn test_rename_overloads() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct Widget {
+        Widget() : size(0) {}
+        Widget(uint32_t a, uint32_t b) : size(a * b) {}
+        uint32_t GetSize() const { return size; }
+        void Resize(uint32_t a) { size = a; }
+        void Resize(uint32_t a, uint32_t b) { size = a * b; }
+        uint32_t size;
+    };
+    "};
+    let rs = quote! {
+        let mut w = ffi::Widget::with_dimensions(2, 3);
+        assert_eq!(w.size(), 6);
+        w.pin_mut().resize_xy(4, 5);
+        assert_eq!(w.size(), 20);
+        w.pin_mut().Resize(7);
+        assert_eq!(w.size(), 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["Widget"],
+        &[],
+        Some(quote! {
+            rename!("Widget::GetSize", "size")
+            rename!("Widget::Widget(uint32_t, uint32_t)", "with_dimensions")
+            rename!("Widget::Resize(uint32_t, uint32_t)", "resize_xy")
+        }),
+    );
+}
+
+#[test]
+fn test_descriptive_overload_names() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    inline uint32_t draw(float) { return 1; }
+    inline uint32_t draw(uint32_t) { return 2; }
+    inline uint32_t draw(uint32_t, uint32_t) { return 3; }
+    inline uint32_t other() { return 4; }
+    struct Canvas {
+        uint32_t paint(uint32_t) const { return 5; }
+        uint32_t paint(float) const { return 6; }
+    };
+    inline Canvas make_canvas() { return Canvas(); }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::draw_float(1.0), 1);
+        assert_eq!(ffi::draw_uint32_t(1), 2);
+        assert_eq!(ffi::draw_uint32_t_uint32_t(1, 2), 3);
+        assert_eq!(ffi::other(), 4);
+        let c = ffi::make_canvas();
+        assert_eq!(c.paint_uint32_t(1), 5);
+        assert_eq!(c.paint_float(1.0), 6);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["draw", "other", "make_canvas", "Canvas"],
+        &[],
+        Some(quote! { overload_naming!(descriptive) }),
+    );
+}
+
+#[test]
+fn test_config_file() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct Point {
+        uint32_t x;
+        uint32_t y;
+    };
+    inline uint32_t sum(Point p) { return p.x + p.y; }
+    inline uint32_t blocked(Point p) { return p.x; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum(ffi::Point { x: 2, y: 3 }), 5);
+    };
+    let config_dir = tempdir().unwrap();
+    let config_path = config_dir.path().join("bindings.toml");
+    std::fs::write(
+        &config_path,
+        indoc! {"
+        generate = [\"sum\", \"blocked\"]
+        generate_pod = [\"Point\"]
+        block = [\"blocked\"]
+        "},
+    )
+    .unwrap();
+    let config_path = config_path.to_str().unwrap();
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &[],
+        &[],
+        Some(quote! { config_file!(#config_path) }),
+    );
+}
+
+#[test]
+fn test_extern_cpp_type() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct Point {
+        uint32_t x;
+        uint32_t y;
+    };
+    inline uint32_t sum(Point p) { return p.x + p.y; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum(other::Point { x: 2, y: 3 }), 5);
+    };
+    // Stands in for the bindings from some other crate.
+    let other = quote! {
+        mod other {
+            #[repr(C)]
+            pub struct Point {
+                pub x: u32,
+                pub y: u32,
+            }
+            unsafe impl cxx::ExternType for Point {
+                type Id = cxx::type_id!("Point");
+                type Kind = cxx::kind::Trivial;
+            }
+        }
+    };
+    run_test_with_extra_rust(
+        "",
+        hdr,
+        rs,
+        &["sum"],
+        &["Point"],
+        Some(quote! { extern_cpp_type!("Point", crate::other::Point) }),
+        other,
+    );
+}
+
+#[test]
+fn test_generate_all() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct Point {
+        uint32_t x;
+        uint32_t y;
+    };
+    union Either {
+        uint32_t a;
+        float b;
+    };
+    inline uint32_t add(uint32_t a, uint32_t b) { return a + b; }
+    inline uint32_t point_sum(const Point& p) { return p.x + p.y; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::add(2, 3), 5);
+    };
+    run_test_ex("", hdr, rs, &[], &[], Some(quote! { generate_all!() }));
+}
+
+#[test]
+fn test_name() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    #include <string>
+    inline uint32_t add(uint32_t a, uint32_t b) { return a + b; }
+    inline std::string greet() { return \"hello\"; }
+    "};
+    let rs = quote! {
+        assert_eq!(graphics_ffi::add(2, 3), 5);
+        assert_eq!(graphics_ffi::greet().as_ref().unwrap(), "hello");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["add", "greet"],
+        &[],
+        Some(quote! { name!(graphics_ffi) }),
+    );
+}
+
+#[test]
+fn test_system_include() {
+    // add() is only declared in mylib/api.h, which is found solely
+    // through the include path.
+    let inc_dir = tempdir().unwrap();
+    std::fs::create_dir(inc_dir.path().join("mylib")).unwrap();
+    std::fs::write(
+        inc_dir.path().join("mylib").join("api.h"),
+        indoc! {"
+        #pragma once
+        #include <cstdint>
+        inline uint32_t add(uint32_t a, uint32_t b) { return a + b; }
+        "},
+    )
+    .unwrap();
+    let inc_flag = format!("-I{}", inc_dir.path().to_str().unwrap());
+    let rs = quote! {
+        assert_eq!(ffi::add(2, 3), 5);
+    };
+    let hexathorpe = Token![#](Span::call_site());
+    run_test_ex(
+        "",
+        "",
+        rs,
+        &["add"],
+        &[],
+        Some(quote! {
+            #hexathorpe include <mylib/api.h>
+            clang_args!(#inc_flag)
+        }),
+    );
+}
+
+#[test]
+fn test_generate_missing_item() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t give_int() {
+            return 5;
+        }
+    "};
+    let rs = quote! {};
+    run_test_expect_fail("", hdr, rs, &["give_ints"], &[]);
+}
+
+#[test]
+fn test_config_file_missing_item() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t give_int() {
+            return 5;
+        }
+    "};
+    let rs = quote! {};
+    let config_dir = tempdir().unwrap();
+    let config_path = config_dir.path().join("bindings.toml");
+    std::fs::write(&config_path, "generate = [\"give_ints\"]\n").unwrap();
+    let config_path = config_path.to_str().unwrap();
+    let err = do_run_test(
+        "",
+        hdr,
+        rs,
+        &[],
+        &[],
+        Some(quote! { config_file!(#config_path) }),
+        None,
+    )
+    .expect_err("Unexpected success");
+    match err {
+        TestError::AutoCxx(err) => {
+            let message = err.to_string();
+            assert!(message.contains(&format!("{}: generate entry give_ints", config_path)));
+            assert!(message.contains("Did you mean give_int?"));
+        }
+        _ => panic!("Expected autocxx to report the missing item"),
+    }
+}
+
+#[test]
+fn test_optional() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <optional>
+        #include <string>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+        inline std::optional<uint32_t> find_int(bool found) {
+            if (found) {
+                return 3;
+            }
+            return std::nullopt;
+        }
+        inline std::optional<Point> find_point(bool found) {
+            if (found) {
+                return Point { 1, 2 };
+            }
+            return std::nullopt;
+        }
+        inline std::optional<std::string> find_name(bool found) {
+            if (found) {
+                return std::string(\"Bob\");
+            }
+            return std::nullopt;
+        }
+        inline uint32_t int_or_zero(std::optional<uint32_t> i) {
+            return i.value_or(0);
+        }
+        inline uint32_t name_length(std::optional<std::string> name) {
+            return name ? name->size() : 0;
+        }
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        assert_eq!(ffi::find_int(true), Some(3));
+        assert_eq!(ffi::find_int(false), None);
+        assert_eq!(ffi::find_point(true).unwrap().y, 2);
+        assert!(ffi::find_point(false).is_none());
+        assert_eq!(ffi::find_name(true).unwrap().to_str().unwrap(), "Bob");
+        assert!(ffi::find_name(false).is_none());
+        assert_eq!(ffi::int_or_zero(Some(4)), 4);
+        assert_eq!(ffi::int_or_zero(None), 0);
+        assert_eq!(ffi::name_length(Some("abc".to_cpp())), 3);
+        assert_eq!(ffi::name_length(None), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &[
+            "find_int",
+            "find_point",
+            "find_name",
+            "int_or_zero",
+            "name_length",
+        ],
+        &["Point"],
+        Some(quote! { cpp_standard!("c++17") }),
+    );
+}
+
+#[test]
+fn test_optional_without_default_constructor() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <optional>
+        struct Point {
+            Point(uint32_t x, uint32_t y) : x(x), y(y) {}
+            uint32_t x;
+            uint32_t y;
+        };
+        inline std::optional<Point> find_point(bool found) {
+            if (found) {
+                return Point(1, 2);
+            }
+            return std::nullopt;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::find_point(true).unwrap().y, 2);
+        assert!(ffi::find_point(false).is_none());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["find_point"],
+        &["Point"],
+        Some(quote! { cpp_standard!("c++17") }),
+    );
+}
+
+#[test]
+fn test_optional_method() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <optional>
+        class Finder {
+        public:
+            Finder() {}
+            std::optional<uint32_t> find(uint32_t i) const {
+                if (i > 2) {
+                    return i * 2;
+                }
+                return std::nullopt;
+            }
+        };
+    "};
+    let rs = quote! {
+        let finder = ffi::Finder::make_unique();
+        assert_eq!(finder.find(3), Some(6));
+        assert_eq!(finder.find(1), None);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["Finder"],
+        &[],
+        Some(quote! { cpp_standard!("c++17") }),
+    );
+}
+
+#[test]
+fn test_string_view() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        #include <string_view>
+        inline uint32_t measure(std::string_view s) {
+            return s.size();
+        }
+        class Person {
+        public:
+            Person() : name(\"Bob\") {}
+            std::string_view get_name() const {
+                return name;
+            }
+        private:
+            std::string name;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::measure("hello"), 5);
+        let person = ffi::Person::make_unique();
+        assert_eq!(person.get_name(), b"Bob");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["measure", "Person"],
+        &[],
+        Some(quote! { cpp_standard!("c++17") }),
+    );
+}
+
+#[test]
+fn test_slice_param() {
+    let hdr = indoc! {"
+        #include <cstddef>
+        #include <cstdint>
+        namespace mylib {
+            inline uint32_t sum(const uint32_t* data, size_t len) {
+                uint32_t total = 0;
+                for (size_t i = 0; i < len; i++) {
+                    total += data[i];
+                }
+                return total;
+            }
+            inline void fill(uint8_t* data, size_t len, uint8_t value) {
+                for (size_t i = 0; i < len; i++) {
+                    data[i] = value;
+                }
+            }
+            class Buffer {
+            public:
+                Buffer() : total(0) {}
+                void append(const uint8_t* bytes, uint32_t count) {
+                    for (uint32_t i = 0; i < count; i++) {
+                        total += bytes[i];
+                    }
+                }
+                uint32_t get_total() const { return total; }
+            private:
+                uint32_t total;
+            };
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::mylib::sum(&[1, 2, 3]), 6);
+        assert_eq!(ffi::mylib::sum(&[]), 0);
+        let mut data = [0u8; 4];
+        ffi::mylib::fill(&mut data[1..], 7);
+        assert_eq!(data, [0, 7, 7, 7]);
+        let mut buffer = ffi::mylib::Buffer::make_unique();
+        buffer.pin_mut().append(&[1, 2, 3, 4]);
+        assert_eq!(buffer.get_total(), 10);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["mylib::sum", "mylib::fill", "mylib::Buffer"],
+        &[],
+        Some(quote! {
+            slice_param!("mylib::sum", "data", "len")
+            slice_param!("mylib::fill", "data", "len")
+            slice_param!("mylib::Buffer::append", "bytes", "count")
+        }),
+    );
+}
+
+#[test]
+fn test_two_slice_params() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t copy(const uint8_t* from, uint16_t from_len, uint32_t offset, uint8_t* to, int to_len) {
+            uint32_t copied = 0;
+            for (uint32_t i = offset; i < from_len && copied < uint32_t(to_len); i++) {
+                to[copied++] = from[i];
+            }
+            return copied;
+        }
+    "};
+    let rs = quote! {
+        let mut to = [0u8; 2];
+        assert_eq!(ffi::copy(&[1, 2, 3, 4], 1, &mut to), 2);
+        assert_eq!(to, [2, 3]);
+        assert!(std::panic::catch_unwind(|| ffi::copy(&[0; 65536], 0, &mut [])).is_err());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["copy"],
+        &[],
+        Some(quote! {
+            slice_param!("copy", "from", "from_len")
+            slice_param!("copy", "to", "to_len")
+        }),
+    );
+}
+
+#[test]
+fn test_slice_param_float_length() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t first(const uint32_t* data, float len) {
+            return len > 0 ? data[0] : 0;
+        }
+    "};
+    let rs = quote! {
+        ffi::first(&[1]);
+    };
+    do_run_test(
+        "",
+        hdr,
+        rs,
+        &["first"],
+        &[],
+        Some(quote! {
+            slice_param!("first", "data", "len")
+        }),
+        None,
+    )
+    .expect_err("Unexpected success");
+}
+
+#[test]
+fn test_span() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <span>
+        inline uint32_t sum(std::span<const uint32_t> values) {
+            uint32_t total = 0;
+            for (auto value : values) {
+                total += value;
+            }
+            return total;
+        }
+        inline void double_all(std::span<uint32_t> values) {
+            for (auto& value : values) {
+                value *= 2;
+            }
+        }
+    "};
+    let rs = quote! {
+        let mut values = [1, 2, 3];
+        ffi::double_all(&mut values);
+        assert_eq!(values, [2, 4, 6]);
+        assert_eq!(ffi::sum(&mut values), 12);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        &["sum", "double_all"],
+        &[],
+        Some(quote! { cpp_standard!("c++20") }),
+    );
+}
+
+#[test]
+fn test_map() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <map>
+        #include <unordered_map>
+        inline std::map<uint32_t, uint32_t> make_squares() {
+            std::map<uint32_t, uint32_t> squares;
+            for (uint32_t i = 1; i < 4; i++) {
+                squares[i] = i * i;
+            }
+            return squares;
+        }
+        inline uint32_t total(const std::map<uint32_t, uint32_t>& m) {
+            uint32_t total = 0;
+            for (const auto& entry : m) {
+                total += entry.second;
+            }
+            return total;
+        }
+        inline std::unordered_map<uint32_t, uint32_t> make_empty() {
+            return std::unordered_map<uint32_t, uint32_t>();
+        }
+    "};
+    let rs = quote! {
+        let mut squares = ffi::make_squares();
+        assert_eq!(squares.len(), 3);
+        assert!(squares.contains(&2));
+        assert!(!squares.contains(&4));
+        assert_eq!(squares.get(&3), Some(&9));
+        assert_eq!(squares.get(&4), None);
+        squares.pin_mut().insert(&4, &16);
+        squares.pin_mut().insert(&1, &2);
+        assert_eq!(ffi::total(&squares), 31);
+        assert_eq!(squares.keys().count(), 4);
+        assert_eq!(squares.iter().map(|(k, v)| k * v).sum::<u32>(), 101);
+        assert!(ffi::make_empty().is_empty());
+    };
+    run_test("", hdr, rs, &["make_squares", "total", "make_empty"], &[]);
+}
+
+#[test]
+fn test_map_of_move_only_values() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <map>
+        #include <memory>
+        #include <string>
+        class Widget {
+        public:
+            Widget(uint32_t size) : size(std::make_unique<uint32_t>(size)) {}
+            uint32_t get_size() const { return *size; }
+        private:
+            std::unique_ptr<uint32_t> size;
+        };
+        inline std::map<std::string, Widget> make_widgets() {
+            std::map<std::string, Widget> widgets;
+            widgets.emplace(\"small\", Widget(1));
+            widgets.emplace(\"big\", Widget(100));
+            return widgets;
+        }
+    "};
+    let rs = quote! {
+        let mut widgets = ffi::make_widgets();
+        assert_eq!(widgets.get(&"small".to_cpp()).unwrap().get_size(), 1);
+        widgets
+            .pin_mut()
+            .insert("small".to_cpp(), ffi::Widget::make_unique(2));
+        widgets
+            .pin_mut()
+            .insert("medium".to_cpp(), ffi::Widget::make_unique(10));
+        let sizes: Vec<_> = widgets
+            .iter()
+            .map(|(name, widget)| (name.to_str().unwrap().to_string(), widget.get_size()))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                ("big".to_string(), 100),
+                ("medium".to_string(), 10),
+                ("small".to_string(), 2)
+            ]
+        );
+    };
+    run_test("", hdr, rs, &["make_widgets", "Widget"], &[]);
+}
+
+#[test]
+fn test_callbacks() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <functional>
+        #include <vector>
+        class Listeners {
+        public:
+            Listeners() {}
+            void add(std::function<void(uint32_t)> listener) {
+                listeners.push_back(listener);
+            }
+            void notify(uint32_t value) const {
+                for (const auto& listener : listeners) {
+                    listener(value);
+                }
+            }
+        private:
+            std::vector<std::function<void(uint32_t)>> listeners;
+        };
+        inline uint32_t apply(const std::function<uint32_t(uint32_t)>& f, uint32_t value) {
+            return f(value);
+        }
+    "};
+    let rs = quote! {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        let total = Arc::new(AtomicU32::new(0));
+        let mut listeners = ffi::Listeners::make_unique();
+        let listener_total = total.clone();
+        listeners.pin_mut().add(move |value| {
+            listener_total.fetch_add(value, Ordering::SeqCst);
+        });
+        listeners.notify(3);
+        listeners.notify(4);
+        assert_eq!(total.load(Ordering::SeqCst), 7);
+        assert_eq!(ffi::apply(|value| value * 2, 21), 42);
+        // The closure is dropped along with the last std::function.
+        drop(listeners);
+        assert_eq!(Arc::strong_count(&total), 1);
+    };
+    run_test("", hdr, rs, &["Listeners", "apply"], &[]);
+}
+
+#[test]
+fn test_pod_pair() {
+    let hdr = indoc! {"
+        #include <utility>
+        inline std::pair<int, float> make_pair() {
+            return std::make_pair(3, 1.5f);
+        }
+        inline float total(std::pair<int, float> p) {
+            return p.first + p.second;
+        }
+        inline int first_of(const std::pair<int, float>& p) {
+            return p.first;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::make_pair(), (autocxx::c_int(3), 1.5));
+        assert_eq!(ffi::total((autocxx::c_int(2), 0.5)), 2.5);
+        assert_eq!(ffi::first_of((autocxx::c_int(4), 0.0)), autocxx::c_int(4));
+    };
+    run_test("", hdr, rs, &["make_pair", "total", "first_of"], &[]);
+}
+
+#[test]
+fn test_non_pod_pair() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <utility>
+        inline std::pair<std::string, int> make_pair() {
+            return std::make_pair(std::string(\"hello\"), 3);
+        }
+    "};
+    let rs = quote! {
+        let pair = ffi::make_pair();
+        assert_eq!(pair.first().to_str().unwrap(), "hello");
+        assert_eq!(*pair.second(), autocxx::c_int(3));
+    };
+    run_test("", hdr, rs, &["make_pair"], &[]);
+}
+
+#[test]
+fn test_pod_tuple() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <tuple>
+        inline std::tuple<uint32_t, float, bool> make_tuple() {
+            return std::make_tuple(3, 1.5f, true);
+        }
+        inline float total(std::tuple<uint32_t, float, bool> t) {
+            return std::get<2>(t) ? std::get<0>(t) + std::get<1>(t) : 0.0f;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::make_tuple(), (3, 1.5, true));
+        assert_eq!(ffi::total((2, 0.5, true)), 2.5);
+        assert_eq!(ffi::total((2, 0.5, false)), 0.0);
+    };
+    run_test("", hdr, rs, &["make_tuple", "total"], &[]);
+}
+
+#[test]
+fn test_non_pod_tuple() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        #include <tuple>
+        inline std::tuple<std::string, uint32_t, std::string> make_tuple() {
+            return std::make_tuple(std::string(\"hello\"), 3, std::string(\"world\"));
+        }
+    "};
+    let rs = quote! {
+        let tuple = ffi::make_tuple();
+        assert_eq!(tuple.get::<0>().to_str().unwrap(), "hello");
+        assert_eq!(*tuple.get::<1>(), 3);
+        assert_eq!(tuple.get::<2>().to_str().unwrap(), "world");
+    };
+    run_test("", hdr, rs, &["make_tuple"], &[]);
+}
+
+#[test]
+fn test_weak_ptr() {
+    let hdr = indoc! {"
+        #include <memory>
+        class Subject {
+        public:
+            Subject() {}
+            int value() const { return 42; }
+        };
+        inline std::shared_ptr<Subject> make_subject() {
+            return std::make_shared<Subject>();
+        }
+        inline std::weak_ptr<Subject> observe(const std::shared_ptr<Subject>& subject) {
+            return subject;
+        }
+        inline bool is_alive(std::weak_ptr<Subject> observer) {
+            return !observer.expired();
+        }
+    "};
+    let rs = quote! {
+        let subject = ffi::make_subject();
+        let observer = ffi::observe(&subject);
+        assert_eq!(observer.upgrade().value(), autocxx::c_int(42));
+        assert!(ffi::is_alive(observer.clone()));
+        let other_observer = subject.downgrade();
+        drop(subject);
+        assert!(observer.upgrade().is_null());
+        assert!(!ffi::is_alive(other_observer));
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["Subject", "make_subject", "observe", "is_alive"],
+        &[],
+    );
+}
+
+#[test]
+fn test_pod_bitfields() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Flags {
+            uint32_t kind : 3;
+            uint32_t count : 12;
+            bool enabled : 1;
+            uint32_t id;
+        };
+        inline Flags make_flags() {
+            Flags f;
+            f.kind = 5;
+            f.count = 1000;
+            f.enabled = true;
+            f.id = 7;
+            return f;
+        }
+        inline uint32_t summarize(Flags f) {
+            return f.kind + f.count + (f.enabled ? 1 : 0) + f.id;
+        }
+    "};
+    let rs = quote! {
+        let mut flags = ffi::make_flags();
+        assert_eq!(flags.kind(), 5);
+        assert_eq!(flags.count(), 1000);
+        assert!(flags.enabled());
+        assert_eq!(flags.id, 7);
+        flags.set_kind(2);
+        flags.set_enabled(false);
+        assert_eq!(flags.count(), 1000);
+        assert_eq!(ffi::summarize(flags), 2 + 1000 + 7);
+    };
+    run_test("", hdr, rs, &["make_flags", "summarize"], &["Flags"]);
+}
+
+#[test]
+fn test_pod_bitfields_in_namespace() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace mylib {
+            struct Flags {
+                uint32_t kind : 3;
+                bool enabled : 1;
+            };
+            inline Flags make_flags() {
+                Flags f;
+                f.kind = 5;
+                f.enabled = true;
+                return f;
+            }
+        }
+    "};
+    let rs = quote! {
+        let mut flags = ffi::mylib::make_flags();
+        assert_eq!(flags.kind(), 5);
+        assert!(flags.enabled());
+        flags.set_kind(2);
+        assert_eq!(flags.kind(), 2);
+    };
+    run_test("", hdr, rs, &["mylib::make_flags"], &["mylib::Flags"]);
+}
+
+#[test]
+fn test_pod_union() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        union Value {
+            int32_t i;
+            float f;
+        };
+        struct Tagged {
+            bool is_float;
+            Value value;
+        };
+        inline Tagged make_int(int32_t i) {
+            Tagged t;
+            t.is_float = false;
+            t.value.i = i;
+            return t;
+        }
+        inline Tagged make_float(float f) {
+            Tagged t;
+            t.is_float = true;
+            t.value.f = f;
+            return t;
+        }
+        inline float as_float(Tagged t) {
+            return t.is_float ? t.value.f : static_cast<float>(t.value.i);
+        }
+    "};
+    let rs = quote! {
+        let t = ffi::make_int(3);
+        assert!(!t.is_float);
+        assert_eq!(unsafe { t.value.i }, 3);
+        let mut t = ffi::make_float(1.5);
+        assert!(t.is_float);
+        assert_eq!(unsafe { t.value.f }, 1.5);
+        assert_eq!(ffi::as_float(t), 1.5);
+        t = ffi::make_int(0);
+        t.value.i = 4;
+        assert_eq!(ffi::as_float(t), 4.0);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["make_int", "make_float", "as_float"],
+        &["Tagged"],
+    );
+}
+
+#[test]
+fn test_non_pod_union() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        #include <string>
+        union Holder {
+            uint32_t i;
+            std::string s;
+            Holder() : i(42) {}
+            ~Holder() {}
+        };
+        inline std::unique_ptr<Holder> make_holder() {
+            return std::make_unique<Holder>();
+        }
+        inline uint32_t get_i(const Holder& h) {
+            return h.i;
+        }
+    "};
+    let rs = quote! {
+        let h = ffi::make_holder();
+        assert_eq!(ffi::get_i(&h), 42);
+    };
+    run_test("", hdr, rs, &["Holder", "make_holder", "get_i"], &[]);
+}
+
 // Yet to test:
 // 6. Ifdef
 // 7. Pointers (including out params)
 // 10. ExcludeUtilities
-// Stuff which requires much more thought:
-// 1. Shared pointers
 // Negative tests:
 // 1. Private methods
 // 2. Private fields
@@ -14,9 +14,10 @@
 
 use crate::types::{make_ident, TypeName};
 use indoc::indoc;
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
-use syn::{parse_quote, Type, TypePath};
+use syn::{parse_quote, GenericArgument, PathArguments, Type, TypePath};
 
 /// Whether this type should be included in the 'prelude'
 /// passed to bindgen, and if so, how.
@@ -25,6 +26,37 @@ enum PreludePolicy {
     Exclude,
     IncludeNormal,
     IncludeTemplated,
+    IncludeTemplatedKeyValue,
+    IncludeTemplatedTuple,
+}
+
+/// The most members a `std::tuple` may have. bindgen only tells us about
+/// as many of a type's template arguments as our stand-in for it in the
+/// prelude has template parameters, and `std::tuple` is variadic, so our
+/// stand-in has this many.
+const MAX_TUPLE_MEMBERS: usize = 12;
+
+/// Types which we pass to or from C++ via wrapper functions which
+/// convert them to something cxx understands.
+#[derive(Debug, PartialEq)]
+enum SpecialHandling {
+    None,
+    /// std::optional, which cxx doesn't support, but which we convert
+    /// to and from a Rust Option.
+    Optional,
+    /// std::string_view, which we accept from Rust as a &str and
+    /// return to Rust as a &[u8].
+    StringView,
+    /// A map type, for which we generate accessor functions.
+    Map,
+    /// std::function, which we create from a Rust closure.
+    Function,
+    /// std::pair, which we pass as a Rust tuple if possible.
+    Pair,
+    /// std::tuple, which we pass as a Rust tuple if possible.
+    Tuple,
+    /// std::span, which we pass as a Rust slice.
+    Span,
 }
 
 /// Details about known special types, mostly primitives.
@@ -47,6 +79,11 @@ struct TypeDetails {
     /// if we encounter such a type as a generic, we'll replace it with
     /// a concrete instantiation.
     is_cxx_container: bool,
+    /// Whether we need to convert this in wrapper functions.
+    special_handling: SpecialHandling,
+    /// Whether this is a numeric type, `bool` or one of our C type
+    /// newtypes, which are cheap to copy.
+    is_primitive: bool,
     /// Any extra non-canonical names
     extra_non_canonical_name: Option<String>,
 }
@@ -70,19 +107,55 @@ impl TypeDetails {
             de_referencicate,
             is_ctype,
             is_cxx_container,
+            special_handling: SpecialHandling::None,
+            is_primitive: false,
             extra_non_canonical_name,
         }
     }
 
+    fn with_special_handling(self, special_handling: SpecialHandling) -> Self {
+        TypeDetails {
+            special_handling,
+            ..self
+        }
+    }
+
+    fn mark_primitive(self) -> Self {
+        TypeDetails {
+            is_primitive: true,
+            ..self
+        }
+    }
+
     fn get_prelude_entry(&self) -> Option<String> {
         match self.prelude_policy {
             PreludePolicy::Exclude => None,
-            PreludePolicy::IncludeNormal | PreludePolicy::IncludeTemplated => {
+            PreludePolicy::IncludeNormal
+            | PreludePolicy::IncludeTemplated
+            | PreludePolicy::IncludeTemplatedKeyValue
+            | PreludePolicy::IncludeTemplatedTuple => {
                 let tn = TypeName::new_from_user_input(&self.rs_name);
                 let cxx_name = tn.get_final_ident();
                 let (templating, payload) = match self.prelude_policy {
-                    PreludePolicy::IncludeNormal => ("", "char* ptr"),
-                    PreludePolicy::IncludeTemplated => ("template<typename T> ", "T* ptr"),
+                    PreludePolicy::IncludeNormal => ("".into(), "char* ptr".into()),
+                    PreludePolicy::IncludeTemplated => {
+                        ("template<typename T> ".into(), "T* ptr".into())
+                    }
+                    PreludePolicy::IncludeTemplatedKeyValue => (
+                        "template<typename K, typename V> ".into(),
+                        "K* key; V* value".into(),
+                    ),
+                    PreludePolicy::IncludeTemplatedTuple => (
+                        format!(
+                            "template<{}> ",
+                            (0..MAX_TUPLE_MEMBERS)
+                                .map(|i| format!("typename T{}", i))
+                                .join(", ")
+                        ),
+                        (0..MAX_TUPLE_MEMBERS)
+                            .map(|i| format!("T{}* m{}", i, i))
+                            .join("; "),
+                    ),
                     _ => unreachable!(),
                 };
                 Some(format!(
@@ -101,6 +174,11 @@ impl TypeDetails {
         }
     }
 
+    /// Whether this is one of the primitive integer types.
+    fn is_integer(&self) -> bool {
+        self.is_primitive && !matches!(self.cpp_name.as_str(), "bool" | "float" | "double")
+    }
+
     fn to_type_path(&self) -> TypePath {
         let segs = self.rs_name.split("::").map(make_ident);
         parse_quote! {
@@ -199,6 +277,118 @@ impl TypeDatabase {
     pub(crate) fn is_cxx_acceptable_generic(&self, ty: &TypeName) -> bool {
         self.get(ty).map(|x| x.is_cxx_container).unwrap_or(false)
     }
+
+    /// Whether this TypePath is std::string_view, which we must convert
+    /// to or from a cxx type in wrapper functions.
+    pub(crate) fn is_string_view(&self, typ: &TypePath) -> bool {
+        let tn = TypeName::from_type_path(typ);
+        self.has_special_handling(&tn, SpecialHandling::StringView)
+    }
+
+    /// If this is a std::map or similar, the types of its keys and values.
+    pub(crate) fn get_map_key_and_value<'a>(&self, ty: &'a Type) -> Option<(&'a Type, &'a Type)> {
+        let args = self.get_type_args(ty, SpecialHandling::Map)?;
+        Some((args.first()?, args.get(1)?))
+    }
+
+    /// If this is a std::pair, the types of its members.
+    pub(crate) fn get_pair_members<'a>(&self, ty: &'a Type) -> Option<(&'a Type, &'a Type)> {
+        let args = self.get_type_args(ty, SpecialHandling::Pair)?;
+        Some((args.first()?, args.get(1)?))
+    }
+
+    /// If this is a std::tuple, the types of its members.
+    pub(crate) fn get_tuple_members<'a>(&self, ty: &'a Type) -> Option<Vec<&'a Type>> {
+        self.get_type_args(ty, SpecialHandling::Tuple)
+    }
+
+    fn get_type_args<'a>(
+        &self,
+        ty: &'a Type,
+        special_handling: SpecialHandling,
+    ) -> Option<Vec<&'a Type>> {
+        match ty {
+            Type::Path(typ)
+                if self.has_special_handling(&TypeName::from_type_path(typ), special_handling) =>
+            {
+                match &typ.path.segments.last().unwrap().arguments {
+                    PathArguments::AngleBracketed(ab) => Some(
+                        ab.args
+                            .iter()
+                            .filter_map(|arg| match arg {
+                                GenericArgument::Type(ty) => Some(ty),
+                                _ => None,
+                            })
+                            .collect(),
+                    ),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `cxx` is unable to hold this type within this container,
+    /// such that we need to wrap it in a newtype of our own. `cxx` supports
+    /// `UniquePtr`s to none of the primitive types, and doesn't know about
+    /// our C type newtypes at all.
+    pub(crate) fn needs_primitive_wrapper(&self, container: &TypeName, ty: &TypeName) -> bool {
+        match self.get(ty) {
+            Some(td) if td.is_primitive => {
+                td.is_ctype
+                    || self
+                        .get(container)
+                        .map(|c| c.rs_name == "cxx::UniquePtr")
+                        .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether this is a numeric type, `bool` or one of our C type
+    /// newtypes, which are cheap to copy but which `cxx` won't hold in
+    /// a `UniquePtr`.
+    pub(crate) fn is_primitive(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Path(typ) => self
+                .get(&TypeName::from_type_path(typ))
+                .map(|td| td.is_primitive)
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Whether this is an integer type, such as the length of a C array.
+    pub(crate) fn is_integer(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Path(typ) => self
+                .get(&TypeName::from_type_path(typ))
+                .map(|td| td.is_integer())
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Whether this is std::optional (or, equivalently, Option).
+    pub(crate) fn is_optional(&self, ty: &TypeName) -> bool {
+        self.has_special_handling(ty, SpecialHandling::Optional)
+    }
+
+    /// Whether this is std::function.
+    pub(crate) fn is_function(&self, ty: &TypeName) -> bool {
+        self.has_special_handling(ty, SpecialHandling::Function)
+    }
+
+    /// Whether this is std::span.
+    pub(crate) fn is_span(&self, ty: &TypeName) -> bool {
+        self.has_special_handling(ty, SpecialHandling::Span)
+    }
+
+    fn has_special_handling(&self, ty: &TypeName, special_handling: SpecialHandling) -> bool {
+        self.get(ty)
+            .map(|td| td.special_handling == special_handling)
+            .unwrap_or(false)
+    }
 }
 
 fn create_type_database() -> TypeDatabase {
@@ -237,6 +427,113 @@ fn create_type_database() -> TypeDatabase {
         true,
         None,
     ));
+    do_insert(TypeDetails::new(
+        "cxx::WeakPtr".into(),
+        "std::weak_ptr".into(),
+        true,
+        PreludePolicy::IncludeTemplated,
+        false,
+        false,
+        true,
+        None,
+    ));
+    do_insert(
+        TypeDetails::new(
+            "std::option::Option".into(),
+            "std::optional".into(),
+            false,
+            PreludePolicy::IncludeTemplated,
+            false,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::Optional),
+    );
+    // Spans are always passed as slices, so this name never appears in
+    // the generated Rust.
+    do_insert(
+        TypeDetails::new(
+            "autocxx::CxxSpan".into(),
+            "std::span".into(),
+            false,
+            PreludePolicy::IncludeTemplated,
+            false,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::Span),
+    );
+    // Maps are always replaced by a concrete instantiation, so these
+    // names never appear in the generated Rust.
+    do_insert(
+        TypeDetails::new(
+            "autocxx::CxxMap".into(),
+            "std::map".into(),
+            false,
+            PreludePolicy::IncludeTemplatedKeyValue,
+            false,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::Map),
+    );
+    do_insert(
+        TypeDetails::new(
+            "autocxx::CxxUnorderedMap".into(),
+            "std::unordered_map".into(),
+            false,
+            PreludePolicy::IncludeTemplatedKeyValue,
+            false,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::Map),
+    );
+    // As with maps, this name never appears in the generated Rust,
+    // since we instead accept a closure.
+    do_insert(
+        TypeDetails::new(
+            "autocxx::CxxFunction".into(),
+            "std::function".into(),
+            false,
+            PreludePolicy::IncludeTemplated,
+            false,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::Function),
+    );
+    do_insert(
+        TypeDetails::new(
+            "autocxx::CxxPair".into(),
+            "std::pair".into(),
+            false,
+            PreludePolicy::IncludeTemplatedKeyValue,
+            false,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::Pair),
+    );
+    do_insert(
+        TypeDetails::new(
+            "autocxx::CxxTuple".into(),
+            "std::tuple".into(),
+            false,
+            PreludePolicy::IncludeTemplatedTuple,
+            false,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::Tuple),
+    );
     do_insert(TypeDetails::new(
         "cxx::CxxString".into(),
         "std::string".into(),
@@ -257,6 +554,21 @@ fn create_type_database() -> TypeDatabase {
         false,
         None,
     ));
+    // This name never appears in the generated Rust, since as with
+    // rust::Str, we always refer to this as &str.
+    do_insert(
+        TypeDetails::new(
+            "autocxx::StringView".into(),
+            "std::string_view".into(),
+            true,
+            PreludePolicy::IncludeNormal,
+            true,
+            false,
+            false,
+            None,
+        )
+        .with_special_handling(SpecialHandling::StringView),
+    );
     do_insert(TypeDetails::new(
         "String".into(),
         "rust::String".into(),
@@ -277,27 +589,50 @@ fn create_type_database() -> TypeDatabase {
         })
         .flatten()
     {
-        do_insert(TypeDetails::new(
-            rust_type,
-            cpp_type,
+        do_insert(
+            TypeDetails::new(
+                rust_type,
+                cpp_type,
+                true,
+                PreludePolicy::Exclude,
+                false,
+                false,
+                false,
+                None,
+            )
+            .mark_primitive(),
+        );
+    }
+    // bindgen already turns size_t, ssize_t, intptr_t, uintptr_t and
+    // ptrdiff_t into these.
+    for (rust_type, cpp_type) in &[("usize", "size_t"), ("isize", "ptrdiff_t")] {
+        do_insert(
+            TypeDetails::new(
+                rust_type.to_string(),
+                cpp_type.to_string(),
+                true,
+                PreludePolicy::Exclude,
+                false,
+                false,
+                false,
+                None,
+            )
+            .mark_primitive(),
+        );
+    }
+    do_insert(
+        TypeDetails::new(
+            "bool".into(),
+            "bool".into(),
             true,
             PreludePolicy::Exclude,
             false,
             false,
             false,
             None,
-        ));
-    }
-    do_insert(TypeDetails::new(
-        "bool".into(),
-        "bool".into(),
-        true,
-        PreludePolicy::Exclude,
-        false,
-        false,
-        false,
-        None,
-    ));
+        )
+        .mark_primitive(),
+    );
 
     do_insert(TypeDetails::new(
         "std::pin::Pin".into(),
@@ -310,34 +645,31 @@ fn create_type_database() -> TypeDatabase {
         None,
     ));
 
-    let mut insert_ctype = |cname: &str| {
-        let td = TypeDetails::new(
-            format!("autocxx::c_{}", cname),
-            cname.into(),
-            true,
-            PreludePolicy::Exclude,
-            false,
-            true,
-            false,
-            Some(format!("std::os::raw::c_{}", cname)),
-        );
-        by_rs_name.insert(TypeName::new_from_user_input(&td.rs_name), td);
+    let mut insert_ctype = |rs_name: &str, cpp_name: &str| {
         let td = TypeDetails::new(
-            format!("autocxx::c_u{}", cname),
-            format!("unsigned {}", cname),
+            format!("autocxx::{}", rs_name),
+            cpp_name.into(),
             true,
             PreludePolicy::Exclude,
             false,
             true,
             false,
-            Some(format!("std::os::raw::c_u{}", cname)),
-        );
+            Some(format!("std::os::raw::{}", rs_name)),
+        )
+        .mark_primitive();
         by_rs_name.insert(TypeName::new_from_user_input(&td.rs_name), td);
     };
 
-    insert_ctype("long");
-    insert_ctype("int");
-    insert_ctype("short");
+    insert_ctype("c_long", "long");
+    insert_ctype("c_ulong", "unsigned long");
+    insert_ctype("c_int", "int");
+    insert_ctype("c_uint", "unsigned int");
+    insert_ctype("c_short", "short");
+    insert_ctype("c_ushort", "unsigned short");
+    insert_ctype("c_longlong", "long long");
+    insert_ctype("c_ulonglong", "unsigned long long");
+    insert_ctype("c_schar", "signed char");
+    insert_ctype("c_uchar", "unsigned char");
 
     let td = TypeDetails::new(
         "f32".into(),
@@ -348,7 +680,8 @@ fn create_type_database() -> TypeDatabase {
         false,
         false,
         None,
-    );
+    )
+    .mark_primitive();
     by_rs_name.insert(TypeName::new_from_user_input(&td.rs_name), td);
 
     let td = TypeDetails::new(
@@ -360,7 +693,8 @@ fn create_type_database() -> TypeDatabase {
         false,
         false,
         None,
-    );
+    )
+    .mark_primitive();
     by_rs_name.insert(TypeName::new_from_user_input(&td.rs_name), td);
 
     let td = TypeDetails::new(
@@ -372,7 +706,8 @@ fn create_type_database() -> TypeDatabase {
         false,
         false,
         None,
-    );
+    )
+    .mark_primitive();
     by_rs_name.insert(TypeName::new_from_user_input(&td.rs_name), td);
 
     let mut by_cppname = HashMap::new();
@@ -18,6 +18,7 @@
 
 mod conversion;
 mod known_types;
+mod missing_items;
 mod parse_callbacks;
 mod parse_file;
 mod rust_pretty_printer;
@@ -29,15 +30,15 @@ mod builder;
 #[cfg(test)]
 mod integration_tests;
 
-use autocxx_parser::{CppInclusion, IncludeCppConfig, UnsafePolicy};
-use conversion::{BridgeConverter, CppCodegenResults};
+use autocxx_parser::{ConfigFileError, CppInclusion, IncludeCppConfig, UnsafePolicy};
+use conversion::{BridgeConverter, CppCodegenResults, Header};
 use parse_callbacks::AutocxxParseCallbacks;
 use proc_macro2::TokenStream as TokenStream2;
 use std::{
     collections::hash_map::DefaultHasher,
     fmt::Display,
     hash::{Hash, Hasher},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use quote::ToTokens;
@@ -49,14 +50,18 @@ use syn::{
 
 use itertools::join;
 use known_types::KNOWN_TYPES;
-use log::{info, warn};
+use log::info;
 
 /// We use a forked version of bindgen - for now.
 /// We hope to unfork.
 use autocxx_bindgen as bindgen;
 
 #[cfg(any(test, feature = "build"))]
-pub use builder::{build, expect_build, BuilderBuild, BuilderError, BuilderResult, BuilderSuccess};
+pub use builder::{
+    build, build_with_clang_args, expect_build, expect_build_with_clang_args, BuilderBuild,
+    BuilderError, BuilderResult, BuilderSuccess,
+};
+pub use missing_items::MissingItem;
 pub use parse_file::{parse_file, ParseError, ParsedFile};
 
 pub use cxx_gen::HEADER;
@@ -102,6 +107,11 @@ pub enum Error {
     /// bindings for everything. That just seems very unlikely to work
     /// in the common case right now.
     NoGenerationRequested,
+    /// A configuration file couldn't be read.
+    ConfigFile(ConfigFileError),
+    /// Some `generate!` or `generate_pod!` entries, or config file entries,
+    /// didn't match anything in the C++ headers.
+    MissingItems(Vec<MissingItem>),
 }
 
 impl Display for Error {
@@ -111,7 +121,14 @@ impl Display for Error {
             Error::Parsing(err) => write!(f, "The Rust file could not be parsede: {}", err)?,
             Error::NoAutoCxxInc => write!(f, "No C++ include directory was provided.")?,
             Error::Conversion(err) => write!(f, "autocxx could not generate the requested bindings. {}", err)?,
-            Error::NoGenerationRequested => write!(f, "No 'generate' or 'generate_pod' directives were found, so we would not generate any Rust bindings despite the inclusion of C++ headers.")?,
+            Error::NoGenerationRequested => write!(f, "No 'generate', 'generate_pod' or 'generate_all' directives were found, so we would not generate any Rust bindings despite the inclusion of C++ headers.")?,
+            Error::ConfigFile(err) => write!(f, "{}", err)?,
+            Error::MissingItems(items) => {
+                write!(f, "Some requested items could not be found:")?;
+                for item in items {
+                    write!(f, "\n  {}", item)?;
+                }
+            }
         }
         Ok(())
     }
@@ -124,6 +141,7 @@ struct GenerationResults {
     item_mod: ItemMod,
     additional_cpp_generator: Option<CppCodegenResults>,
     inc_dirs: Vec<PathBuf>,
+    cpp_flags: Vec<String>,
 }
 enum State {
     NotGenerated,
@@ -243,6 +261,9 @@ pub trait RebuildDependencyRecorder: std::fmt::Debug {
 pub struct IncludeCppEngine {
     config: IncludeCppConfig,
     state: State,
+    /// The `.rs` file in which this `include_cpp!` was found, if any,
+    /// so that errors can say where to look.
+    source_file: Option<PathBuf>,
 }
 
 impl Parse for IncludeCppEngine {
@@ -253,7 +274,11 @@ impl Parse for IncludeCppEngine {
         } else {
             State::NotGenerated
         };
-        Ok(Self { config, state })
+        Ok(Self {
+            config,
+            state,
+            source_file: None,
+        })
     }
 }
 
@@ -262,19 +287,60 @@ impl IncludeCppEngine {
         mac.parse_body::<IncludeCppEngine>().map_err(Error::Parsing)
     }
 
+    pub(crate) fn set_source_file(&mut self, path: &Path) {
+        self.source_file = Some(path.to_path_buf());
+    }
+
+    /// Builds the preamble of defines and includes which is seen both
+    /// by bindgen and by our generated C++. Defines always come first
+    /// so that every header is parsed with the same preprocessor state.
     fn build_header(&self) -> String {
-        join(
-            self.config.inclusions.iter().map(|incl| match incl {
-                CppInclusion::Define(symbol) => format!("#define {}\n", symbol),
-                CppInclusion::Header(path) => format!("#include \"{}\"\n", path),
-            }),
-            "",
-        )
+        let defines = self.config.inclusions.iter().filter_map(|incl| match incl {
+            CppInclusion::Define(symbol, Some(value)) => {
+                Some(format!("#define {} {}\n", symbol, value))
+            }
+            CppInclusion::Define(symbol, None) => Some(format!("#define {}\n", symbol)),
+            CppInclusion::Header(_) | CppInclusion::SystemHeader(_) => None,
+        });
+        let headers = self
+            .generate_include_list()
+            .into_iter()
+            .map(|hdr| format!("{}\n", hdr.include_stmt()));
+        join(defines.chain(headers), "")
+    }
+
+    fn has_defines(&self) -> bool {
+        self.config
+            .inclusions
+            .iter()
+            .any(|incl| matches!(incl, CppInclusion::Define(..)))
+    }
+
+    /// Works out the flags which bindgen and the C++ compiler must agree
+    /// upon: the C++ standard and any extra clang arguments. Those given
+    /// within the `include_cpp!` take precedence over those from the
+    /// build system.
+    fn determine_cpp_flags(&self, extra_clang_args: &[String]) -> Vec<String> {
+        let mut flags = vec!["-std=c++14".to_string()];
+        flags.extend(extra_clang_args.iter().cloned());
+        if let Some(cpp_standard) = &self.config.cpp_standard {
+            flags.push(format!("-std={}", cpp_standard));
+        }
+        flags.extend(self.config.extra_clang_args.iter().cloned());
+        // Only the last -std= counts, so don't confuse matters by passing the others.
+        let last_std = flags.iter().rposition(|f| f.starts_with("-std="));
+        flags
+            .into_iter()
+            .enumerate()
+            .filter(|(i, f)| !f.starts_with("-std=") || Some(*i) == last_std)
+            .map(|(_, f)| f)
+            .collect()
     }
 
-    fn make_bindgen_builder(&self, inc_dirs: &[PathBuf]) -> bindgen::Builder {
+    fn make_bindgen_builder(&self, inc_dirs: &[PathBuf], cpp_flags: &[String]) -> bindgen::Builder {
         let mut builder = bindgen::builder()
-            .clang_args(&["-x", "c++", "-std=c++14", "-DBINDGEN"])
+            .clang_args(&["-x", "c++", "-DBINDGEN"])
+            .clang_args(cpp_flags)
             .derive_copy(false)
             .derive_debug(false)
             .default_enum_style(bindgen::EnumVariation::Rust {
@@ -283,6 +349,12 @@ impl IncludeCppEngine {
             .enable_cxx_namespaces()
             .disable_nested_struct_naming()
             .generate_inline_functions(true)
+            // Keep the size of array parameters, so that we can
+            // represent them as Rust arrays rather than pointers.
+            .array_pointers_in_arguments(true)
+            // Give size_t and ssize_t the same platform-independent
+            // meaning as uintptr_t and ptrdiff_t already have.
+            .size_t_is_usize(true)
             .layout_tests(false); // TODO revisit later
         for item in known_types::get_initial_blocklist() {
             builder = builder.blocklist_item(item);
@@ -295,8 +367,17 @@ impl IncludeCppEngine {
             )
         }));
 
+        builder
+    }
+
+    fn add_allowlist_to_bindgen_builder(&self, mut builder: bindgen::Builder) -> bindgen::Builder {
         // 3. Passes allowlist and other options to the bindgen::Builder equivalent
         //    to --output-style=cxx --allowlist=<as passed in>
+        //    With generate_all! we give bindgen no allowlist at all, so that
+        //    it emits everything except the initial blocklist.
+        if self.config.type_config.is_generate_all() {
+            return builder;
+        }
         for a in self.config.type_config.allowlist() {
             // TODO - allowlist type/functions/separately
             builder = builder
@@ -304,6 +385,13 @@ impl IncludeCppEngine {
                 .allowlist_function(a)
                 .allowlist_var(a);
         }
+        for ns in self.config.type_config.allowlisted_namespaces() {
+            let everything_within = format!("{}::.*", ns);
+            builder = builder
+                .allowlist_type(&everything_within)
+                .allowlist_function(&everything_within)
+                .allowlist_var(&everything_within);
+        }
 
         builder
     }
@@ -315,6 +403,15 @@ impl IncludeCppEngine {
         builder
     }
 
+    /// Reads further configuration from a TOML file, just as if it
+    /// had been named in a `config_file!` directive. This must be
+    /// called before [IncludeCppEngine::generate].
+    pub fn merge_config_file(&mut self, path: &Path) -> Result<()> {
+        self.config
+            .merge_config_file(path)
+            .map_err(Error::ConfigFile)
+    }
+
     pub fn get_rs_filename(&self) -> String {
         let mut hasher = DefaultHasher::new();
         self.config.hash(&mut hasher);
@@ -343,14 +440,53 @@ impl IncludeCppEngine {
         syn::parse_str::<ItemMod>(&bindings).map_err(Error::Parsing)
     }
 
-    fn generate_include_list(&self) -> Vec<String> {
+    /// Complains about any `generate!` or `generate_pod!` entries which
+    /// matched nothing that bindgen found. Otherwise, we'd silently
+    /// generate nothing for them.
+    fn check_allowlist_items_found(
+        &self,
+        bindings: &ItemMod,
+        inc_dirs: &[PathBuf],
+        cpp_flags: &[String],
+    ) -> Result<()> {
+        let found = missing_items::find_cpp_names(bindings);
+        let unmatched = self
+            .config
+            .type_config
+            .unmatched_allowlist_entries(found.iter().map(String::as_str));
+        if unmatched.is_empty() {
+            return Ok(());
+        }
+        // bindgen only told us about the things we asked for, so in order
+        // to make suggestions, ask it again for everything in the headers.
+        // If that fails, we simply won't make any suggestions.
+        let everything = self
+            .inject_header_into_bindgen(self.make_bindgen_builder(inc_dirs, cpp_flags))
+            .generate()
+            .ok()
+            .and_then(|bindings| self.parse_bindings(bindings).ok())
+            .map(|bindings| missing_items::find_cpp_names(&bindings))
+            .unwrap_or_default();
+        Err(Error::MissingItems(
+            unmatched
+                .into_iter()
+                .map(|(item, origin)| MissingItem {
+                    item: item.to_string(),
+                    file: self.source_file.clone(),
+                    origin: origin.clone(),
+                    suggestions: missing_items::find_suggestions(item, &everything),
+                })
+                .collect(),
+        ))
+    }
+
+    fn generate_include_list(&self) -> Vec<Header> {
         let mut include_list = Vec::new();
         for incl in &self.config.inclusions {
             match incl {
-                CppInclusion::Header(ref hdr) => {
-                    include_list.push(hdr.clone());
-                }
-                CppInclusion::Define(_) => warn!("Currently no way to define! within cxx"),
+                CppInclusion::Header(ref hdr) => include_list.push(Header::user(hdr)),
+                CppInclusion::SystemHeader(ref hdr) => include_list.push(Header::system(hdr)),
+                CppInclusion::Define(..) => {}
             }
         }
         include_list
@@ -365,6 +501,17 @@ impl IncludeCppEngine {
         &mut self,
         inc_dirs: Vec<PathBuf>,
         dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+    ) -> Result<()> {
+        self.generate_with_clang_args(inc_dirs, &[], dep_recorder)
+    }
+
+    /// Like [IncludeCppEngine::generate], but passes some extra arguments
+    /// to clang, for instance `-std=c++17`.
+    pub fn generate_with_clang_args(
+        &mut self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[String],
+        dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
     ) -> Result<()> {
         // If we are in parse only mode, do nothing. This is used for
         // doc tests to ensure the parsing is valid, but we can't expect
@@ -379,8 +526,15 @@ impl IncludeCppEngine {
             return Err(Error::NoGenerationRequested);
         }
 
-        let mut builder = self.make_bindgen_builder(&inc_dirs);
+        let cpp_flags = self.determine_cpp_flags(extra_clang_args);
+        let mut builder =
+            self.add_allowlist_to_bindgen_builder(self.make_bindgen_builder(&inc_dirs, &cpp_flags));
         if let Some(dep_recorder) = dep_recorder {
+            // Changes to config files should trigger a rebuild just as
+            // changes to headers do.
+            for config_file in &self.config.config_files {
+                dep_recorder.record_header_file_dependency(&config_file.to_string_lossy());
+            }
             builder = builder.parse_callbacks(Box::new(AutocxxParseCallbacks(dep_recorder)));
         }
         let bindings = self
@@ -388,25 +542,36 @@ impl IncludeCppEngine {
             .generate()
             .map_err(Error::Bindgen)?;
         let bindings = self.parse_bindings(bindings)?;
+        self.check_allowlist_items_found(&bindings, &inc_dirs, &cpp_flags)?;
 
         let include_list = self.generate_include_list();
-        let converter = BridgeConverter::new(&include_list, &self.config.type_config);
+        let autocxxgen_header_name = self.generated_header_name("autocxxgen.h");
+        let cxxgen_header_name = self.generated_header_name("cxxgen.h");
+        let converter = BridgeConverter::new(
+            &include_list,
+            &autocxxgen_header_name,
+            &cxxgen_header_name,
+            &self.config.type_config,
+        );
 
         let conversion = converter
             .convert(
                 bindings,
                 self.config.exclude_utilities,
                 self.config.unsafe_policy.clone(),
+                self.config.overload_naming,
                 self.build_header(),
+                self.has_defines(),
             )
             .map_err(Error::Conversion)?;
         let mut items = conversion.rs;
+        let mod_name = self.config.get_mod_name();
         let mut new_bindings: ItemMod = parse_quote! {
             #[allow(non_snake_case)]
             #[allow(dead_code)]
             #[allow(non_upper_case_globals)]
             #[allow(non_camel_case_types)]
-            mod ffi {
+            mod #mod_name {
             }
         };
         new_bindings.content.as_mut().unwrap().1.append(&mut items);
@@ -418,6 +583,7 @@ impl IncludeCppEngine {
             item_mod: new_bindings,
             additional_cpp_generator: conversion.cpp,
             inc_dirs,
+            cpp_flags,
         }));
         Ok(())
     }
@@ -434,7 +600,7 @@ impl IncludeCppEngine {
                 let cxx_generated = cxx_gen::generate_header_and_cc(rs, &opt)?;
                 files.push(CppFilePair {
                     header: cxx_generated.header,
-                    header_name: "cxxgen.h".to_string(),
+                    header_name: self.generated_header_name("cxxgen.h"),
                     implementation: cxx_generated.implementation,
                 });
 
@@ -445,7 +611,7 @@ impl IncludeCppEngine {
                         let declarations = format!("#pragma once\n{}", additional_cpp.declarations);
                         files.push(CppFilePair {
                             header: declarations.as_bytes().to_vec(),
-                            header_name: "autocxxgen.h".to_string(),
+                            header_name: self.generated_header_name("autocxxgen.h"),
                             implementation: additional_cpp.definitions.as_bytes().to_vec(),
                         });
                         info!("Additional C++ decls:\n{}", declarations);
@@ -457,6 +623,16 @@ impl IncludeCppEngine {
         Ok(GeneratedCpp(files))
     }
 
+    /// If the user chose a name for the generated mod, it's used for the
+    /// generated headers too, so that the headers from several `include_cpp!`
+    /// blocks can live in the same directory.
+    fn generated_header_name(&self, base_name: &str) -> String {
+        match &self.config.mod_name {
+            Some(mod_name) => format!("{}_{}", mod_name, base_name),
+            None => base_name.to_string(),
+        }
+    }
+
     /// Return the include directories used for this include_cpp invocation.
     pub fn include_dirs(&self) -> &Vec<PathBuf> {
         match &self.state {
@@ -464,4 +640,13 @@ impl IncludeCppEngine {
             _ => panic!("Must call generate() before include_dirs()"),
         }
     }
+
+    /// Return the flags, such as the C++ standard, which were passed to
+    /// bindgen and which must also be passed to the C++ compiler.
+    pub fn cpp_flags(&self) -> &[String] {
+        match &self.state {
+            State::Generated(gen_results) => &gen_results.cpp_flags,
+            _ => panic!("Must call generate() before cpp_flags()"),
+        }
+    }
 }
@@ -0,0 +1,221 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt::Display, path::PathBuf};
+
+use autocxx_parser::AllowlistOrigin;
+use itertools::Itertools;
+use syn::{ForeignItem, Ident, ImplItem, Item, ItemMod, LitStr, Type};
+
+use crate::types::{Namespace, TypeName};
+
+/// How similar a C++ name must be to a missing item before we'll
+/// suggest it, from 0 (not at all) to 1 (identical).
+const SUGGESTION_THRESHOLD: f64 = 0.6;
+const MAX_SUGGESTIONS: usize = 3;
+
+/// An entry in `generate!` or `generate_pod!`, or in the corresponding
+/// list of a config file, which matched nothing in the C++ headers.
+#[derive(Debug)]
+pub struct MissingItem {
+    /// The name or pattern which the user gave.
+    pub item: String,
+    /// The `.rs` file containing the `include_cpp!`, if known.
+    pub file: Option<PathBuf>,
+    /// Where it was given.
+    pub origin: AllowlistOrigin,
+    /// Similar names which were found in the headers.
+    pub suggestions: Vec<String>,
+}
+
+impl MissingItem {
+    fn message(&self) -> String {
+        let mut message = format!("{} was not found in the C++ headers.", self.item);
+        if !self.suggestions.is_empty() {
+            message += &format!(" Did you mean {}?", self.suggestions.iter().join(" or "));
+        }
+        message
+    }
+}
+
+impl Display for MissingItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let start = match &self.origin {
+            AllowlistOrigin::Span(span) => span.start(),
+            AllowlistOrigin::ConfigFile(path, key) => {
+                return write!(f, "{}: {} entry {}", path.display(), key, self.message())
+            }
+        };
+        match &self.file {
+            Some(file) => write!(
+                f,
+                "{}:{}:{}: {}",
+                file.display(),
+                start.line,
+                start.column + 1,
+                self.message()
+            ),
+            None => write!(
+                f,
+                "line {}, column {}: {}",
+                start.line,
+                start.column + 1,
+                self.message()
+            ),
+        }
+    }
+}
+
+/// Lists the C++ names of everything which bindgen generated.
+pub(crate) fn find_cpp_names(bindgen_mod: &ItemMod) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Some((_, items)) = &bindgen_mod.content {
+        for item in items {
+            // With namespaces enabled, bindgen puts everything in
+            // a mod called 'root'.
+            if let Item::Mod(root_mod) = item {
+                if let Some((_, items)) = &root_mod.content {
+                    find_cpp_names_in_mod(items, &Namespace::new(), &mut names);
+                }
+            }
+        }
+    }
+    names
+}
+
+fn find_cpp_names_in_mod(items: &[Item], ns: &Namespace, names: &mut Vec<String>) {
+    let qualify = |id: &str| TypeName::new(ns, id).to_cpp_name();
+    for item in items {
+        match item {
+            Item::Struct(s) => names.push(qualify(&s.ident.to_string())),
+            Item::Enum(e) => names.push(qualify(&e.ident.to_string())),
+            Item::Union(u) => names.push(qualify(&u.ident.to_string())),
+            Item::Type(t) => names.push(qualify(&t.ident.to_string())),
+            Item::Const(c) => names.push(qualify(&c.ident.to_string())),
+            Item::ForeignMod(fm) => {
+                for fi in &fm.items {
+                    if let ForeignItem::Fn(f) = fi {
+                        for name in fn_names(&f.sig.ident, &f.attrs) {
+                            names.push(qualify(&name));
+                        }
+                    }
+                }
+            }
+            Item::Impl(imp) => {
+                // Methods are listed within bindgen's impl blocks, so
+                // we can suggest those too.
+                if let Type::Path(typ) = imp.self_ty.as_ref() {
+                    if let Some(ty) = typ.path.segments.last() {
+                        for impl_item in &imp.items {
+                            if let ImplItem::Method(m) = impl_item {
+                                for name in fn_names(&m.sig.ident, &m.attrs) {
+                                    names.push(qualify(&format!("{}::{}", ty.ident, name)));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Item::Mod(m) => {
+                if let Some((_, items)) = &m.content {
+                    find_cpp_names_in_mod(items, &ns.push(m.ident.to_string()), names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// People may refer to a function by either its C++ name or the
+/// name bindgen gave it (e.g. for overloads, or names which are Rust
+/// keywords), so we accept both.
+fn fn_names(ident: &Ident, attrs: &[syn::Attribute]) -> Vec<String> {
+    std::iter::once(ident.to_string())
+        .chain(
+            attrs
+                .iter()
+                .filter(|a| a.path.is_ident("bindgen_original_name"))
+                .filter_map(|a| a.parse_args::<LitStr>().ok())
+                .map(|ls| ls.value()),
+        )
+        .collect()
+}
+
+/// Picks out the names most similar to a missing item. A name is
+/// similar if either the whole thing is, or just its final segment,
+/// since a common mistake is to get the namespace wrong.
+pub(crate) fn find_suggestions(item: &str, cpp_names: &[String]) -> Vec<String> {
+    let final_segment = |name: &str| name.rsplit("::").next().unwrap_or_default().to_string();
+    let item_final_segment = final_segment(item);
+    cpp_names
+        .iter()
+        .unique()
+        .map(|name| {
+            let similarity = strsim::normalized_damerau_levenshtein(item, name).max(
+                strsim::normalized_damerau_levenshtein(&item_final_segment, &final_segment(name)),
+            );
+            (similarity, name)
+        })
+        .filter(|(similarity, _)| *similarity >= SUGGESTION_THRESHOLD)
+        .sorted_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap())
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_cpp_names, find_suggestions};
+    use syn::{parse_quote, ItemMod};
+
+    #[test]
+    fn test_find_cpp_names() {
+        let bindgen_mod: ItemMod = parse_quote! {
+            mod bindgen {
+                pub mod root {
+                    pub struct Bob {}
+                    pub mod A {
+                        pub struct Fred {}
+                        impl Fred {
+                            #[bindgen_original_name("get")]
+                            pub unsafe fn get1(&self) {}
+                        }
+                        extern "C" {
+                            #[bindgen_original_name("foo")]
+                            pub fn foo1();
+                        }
+                    }
+                }
+            }
+        };
+        assert_eq!(
+            find_cpp_names(&bindgen_mod),
+            vec![
+                "Bob",
+                "A::Fred",
+                "A::Fred::get1",
+                "A::Fred::get",
+                "A::foo1",
+                "A::foo"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_suggestions() {
+        let names: Vec<String> = vec!["Foo".into(), "Bar".into(), "A::Fooo".into()];
+        assert_eq!(find_suggestions("Fooo", &names), vec!["A::Fooo", "Foo"]);
+        assert!(find_suggestions("Completely_different", &names).is_empty());
+    }
+}
@@ -55,20 +55,21 @@ impl Display for ParseError {
 /// Parse a Rust file, and spot any include_cpp macros within it.
 pub fn parse_file<P1: AsRef<Path>>(rs_file: P1) -> Result<ParsedFile, ParseError> {
     let mut source = String::new();
-    let mut file = std::fs::File::open(rs_file).map_err(ParseError::FileOpen)?;
+    let mut file = std::fs::File::open(&rs_file).map_err(ParseError::FileOpen)?;
     file.read_to_string(&mut source)
         .map_err(ParseError::FileRead)?;
     let source = syn::parse_file(&source).map_err(ParseError::Syntax)?;
-    parse_file_contents(source)
+    parse_file_contents(source, rs_file.as_ref())
 }
 
-fn parse_file_contents(source: syn::File) -> Result<ParsedFile, ParseError> {
+fn parse_file_contents(source: syn::File, rs_file: &Path) -> Result<ParsedFile, ParseError> {
     let mut results = Vec::new();
     for item in source.items {
         if let Item::Macro(ref mac) = item {
             if mac.mac.path.is_ident("include_cpp") {
-                let include_cpp = crate::IncludeCppEngine::new_from_syn(mac.mac.clone())
+                let mut include_cpp = crate::IncludeCppEngine::new_from_syn(mac.mac.clone())
                     .map_err(ParseError::AutocxxCodegenError)?;
+                include_cpp.set_source_file(rs_file);
                 results.push(Segment::Autocxx(include_cpp));
                 continue;
             }
@@ -116,6 +117,16 @@ impl ParsedFile {
         &mut self,
         autocxx_inc: Vec<PathBuf>,
         dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+    ) -> Result<(), ParseError> {
+        self.resolve_all_with_clang_args(autocxx_inc, &[], dep_recorder)
+    }
+
+    /// Like [ParsedFile::resolve_all], but passes some extra arguments to clang.
+    pub fn resolve_all_with_clang_args(
+        &mut self,
+        autocxx_inc: Vec<PathBuf>,
+        extra_clang_args: &[String],
+        dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
     ) -> Result<(), ParseError> {
         let inner_dep_recorder: Option<Rc<dyn RebuildDependencyRecorder>> =
             dep_recorder.map(Rc::from);
@@ -128,7 +139,7 @@ impl ParsedFile {
                 ))),
             };
             include_cpp
-                .generate(autocxx_inc.clone(), dep_recorder)
+                .generate_with_clang_args(autocxx_inc.clone(), extra_clang_args, dep_recorder)
                 .map_err(ParseError::AutocxxCodegenError)?
         }
         Ok(())
@@ -18,6 +18,7 @@ use std::{fmt::Display, sync::Arc};
 use syn::{parse_quote, Ident, PathSegment, TypePath};
 
 use crate::known_types::KNOWN_TYPES;
+use autocxx_parser::TypeConfig;
 
 pub(crate) fn make_ident<S: AsRef<str>>(id: S) -> Ident {
     Ident::new(id.as_ref(), Span::call_site())
@@ -48,11 +49,15 @@ impl Namespace {
         self.0.iter()
     }
 
-    #[cfg(test)]
     pub(crate) fn from_user_input(input: &str) -> Self {
         Self(Arc::new(input.split("::").map(|x| x.to_string()).collect()))
     }
 
+    /// Whether this namespace is `outer`, or nested somewhere within it.
+    pub(crate) fn is_within(&self, outer: &Namespace) -> bool {
+        self.0.starts_with(&outer.0)
+    }
+
     pub(crate) fn depth(&self) -> usize {
         self.0.len()
     }
@@ -123,6 +128,15 @@ impl TypeName {
         unreachable!()
     }
 
+    /// Whether this type is allowlisted, either by name or because
+    /// it lives within a namespace given to `generate_ns!`.
+    pub(crate) fn is_on_allowlist(&self, type_config: &TypeConfig) -> bool {
+        type_config.is_on_allowlist(&self.to_cpp_name())
+            || type_config
+                .allowlisted_namespaces()
+                .any(|ns| self.0.is_within(&Namespace::from_user_input(ns)))
+    }
+
     /// Create from a type encountered in the code.
     pub(crate) fn new(ns: &Namespace, id: &str) -> Self {
         Self(ns.clone(), id.to_string())
@@ -196,6 +210,17 @@ impl TypeName {
     pub(crate) fn is_cvoid(&self) -> bool {
         self.to_cpp_name() == "std::os::raw::c_void"
     }
+
+    /// Whether this is the storage type `bindgen` generates for bitfields.
+    pub(crate) fn is_bitfield_unit(&self) -> bool {
+        !self.has_namespace() && self.1 == "__BindgenBitfieldUnit"
+    }
+
+    /// Whether this is the marker type `bindgen` generates for each
+    /// member of a union which it represents as a struct.
+    pub(crate) fn is_union_field(&self) -> bool {
+        !self.has_namespace() && self.1 == "__BindgenUnionField"
+    }
 }
 
 impl Display for TypeName {
@@ -210,7 +235,7 @@ impl Display for TypeName {
 
 #[cfg(test)]
 mod tests {
-    use super::TypeName;
+    use super::{Namespace, TypeName};
 
     #[test]
     fn test_ints() {
@@ -220,4 +245,13 @@ mod tests {
             "uint64_t"
         );
     }
+
+    #[test]
+    fn test_namespace_is_within() {
+        let outer = Namespace::from_user_input("mylib::geometry");
+        assert!(Namespace::from_user_input("mylib::geometry").is_within(&outer));
+        assert!(Namespace::from_user_input("mylib::geometry::detail").is_within(&outer));
+        assert!(!Namespace::from_user_input("mylib").is_within(&outer));
+        assert!(!Namespace::from_user_input("mylib::geometryx").is_within(&outer));
+    }
 }
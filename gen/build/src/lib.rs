@@ -13,8 +13,8 @@
 // limitations under the License.
 
 use autocxx_engine::{
-    build as engine_build, expect_build as engine_expect_build, BuilderBuild, BuilderError,
-    RebuildDependencyRecorder,
+    build_with_clang_args as engine_build, expect_build_with_clang_args as engine_expect_build,
+    BuilderBuild, BuilderError, RebuildDependencyRecorder,
 };
 use std::io::Write;
 use std::{ffi::OsStr, path::Path};
@@ -24,6 +24,22 @@ use std::{ffi::OsStr, path::Path};
 /// You need to provide the Rust file path and the iterator of paths
 /// which should be used as include directories.
 pub fn build<P1, I, T>(rs_file: P1, autocxx_incs: I) -> Result<BuilderBuild, BuilderError>
+where
+    P1: AsRef<Path>,
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    build_with_clang_args(rs_file, autocxx_incs, &[])
+}
+
+/// Like [build], but passes some extra arguments to clang, for instance
+/// `-std=c++17`. These are used both to parse the headers and to
+/// compile the C++ using the returned cc::Build, so that the two agree.
+pub fn build_with_clang_args<P1, I, T>(
+    rs_file: P1,
+    autocxx_incs: I,
+    extra_clang_args: &[&str],
+) -> Result<BuilderBuild, BuilderError>
 where
     P1: AsRef<Path>,
     I: IntoIterator<Item = T>,
@@ -33,6 +49,7 @@ where
     engine_build(
         rs_file,
         autocxx_incs,
+        extra_clang_args,
         Some(Box::new(CargoRebuildDependencyRecorder)),
     )
     .map(|r| r.0)
@@ -41,6 +58,21 @@ where
 /// Builds successfully, or exits the process displaying a suitable
 /// message.
 pub fn expect_build<P1, I, T>(rs_file: P1, autocxx_incs: I) -> BuilderBuild
+where
+    P1: AsRef<Path>,
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    expect_build_with_clang_args(rs_file, autocxx_incs, &[])
+}
+
+/// Like [expect_build], but passes some extra arguments to clang.
+/// See [build_with_clang_args].
+pub fn expect_build_with_clang_args<P1, I, T>(
+    rs_file: P1,
+    autocxx_incs: I,
+    extra_clang_args: &[&str],
+) -> BuilderBuild
 where
     P1: AsRef<Path>,
     I: IntoIterator<Item = T>,
@@ -50,6 +82,7 @@ where
     engine_expect_build(
         rs_file,
         autocxx_incs,
+        extra_clang_args,
         Some(Box::new(CargoRebuildDependencyRecorder)),
     )
     .0
@@ -79,6 +79,32 @@ fn test_gen_fixed_num() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_gen_with_config() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = TempDir::new("example")?;
+    let demo_code_dir = tmp_dir.path().join("demo");
+    std::fs::create_dir(&demo_code_dir).unwrap();
+    write_to_file(&demo_code_dir, "input.h", INPUT_H.as_bytes());
+    write_to_file(&demo_code_dir, "main.rs", MAIN_RS.as_bytes());
+    write_to_file(&demo_code_dir, "bindings.toml", b"block = [\"DoMath\"]\n");
+    let demo_rs = demo_code_dir.join("main.rs");
+    let mut cmd = Command::cargo_bin("autocxx-gen")?;
+    cmd.arg("-I")
+        .arg(demo_code_dir.to_str().unwrap())
+        .arg(demo_rs)
+        .arg("--outdir")
+        .arg(tmp_dir.path().to_str().unwrap())
+        .arg("--config")
+        .arg(demo_code_dir.join("bindings.toml").to_str().unwrap())
+        .arg("--gen-cpp")
+        .arg("--gen-rs-include")
+        .arg("--fix-rs-include-name")
+        .assert()
+        .success();
+    assert_contentful(&tmp_dir, "gen0.include.rs");
+    Ok(())
+}
+
 fn write_to_file(dir: &PathBuf, filename: &str, content: &[u8]) {
     let path = dir.join(filename);
     let mut f = File::create(&path).expect("Unable to create file");
@@ -103,6 +103,23 @@ fn main() {
                 .help("include path")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("clang-args")
+                .long("clang-args")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("CLANG ARG")
+                .allow_hyphen_values(true)
+                .help("extra argument to pass to clang, e.g. -std=c++17. Pass the same to your C++ compiler")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("TOML FILE")
+                .help("read further configuration for every include_cpp! from this file, as if named in a config_file! directive. If used with --gen-rs-include, you must also use --fix-rs-include-name")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("cpp-extension")
                 .long("cpp-extension")
@@ -155,6 +172,18 @@ fn main() {
         .get_matches();
     let mut parsed_file = parse_file(matches.value_of("INPUT").unwrap())
         .expect("Unable to parse Rust file and interpret autocxx macro");
+    if let Some(config) = matches.value_of_os("config") {
+        // The name of the .rs file is derived from the configuration,
+        // which autocxx_macro won't be able to see in this case.
+        if matches.is_present("gen-rs-include") && !matches.is_present("fix-rs-include-name") {
+            panic!("--config requires --fix-rs-include-name when used with --gen-rs-include");
+        }
+        for include_cpp in parsed_file.get_autocxxes_mut() {
+            include_cpp
+                .merge_config_file(Path::new(config))
+                .expect("Unable to read config file");
+        }
+    }
     let incs = matches
         .values_of("inc")
         .unwrap_or_default()
@@ -163,8 +192,13 @@ fn main() {
     // In future, we should provide an option to write a .d file here
     // by passing a callback into the dep_recorder parameter here.
     // https://github.com/google/autocxx/issues/56
+    let extra_clang_args: Vec<String> = matches
+        .values_of("clang-args")
+        .unwrap_or_default()
+        .map(str::to_string)
+        .collect();
     parsed_file
-        .resolve_all(incs, None)
+        .resolve_all_with_clang_args(incs, &extra_clang_args, None)
         .expect("Unable to resolve macro");
     let outdir: PathBuf = matches.value_of_os("outdir").unwrap().into();
     let desired_number = matches
@@ -13,13 +13,16 @@
 // limitations under the License.
 
 use proc_macro2::Span;
+use regex::Regex;
 use syn::Result as ParseResult;
 use syn::{
     parse::{Parse, ParseStream},
     Token,
 };
 
-use crate::type_config::TypeConfig;
+use crate::config_file::{ConfigFile, ConfigFileError};
+use crate::type_config::{AllowlistOrigin, TypeConfig};
+use std::path::{Path, PathBuf};
 
 #[derive(PartialEq, Clone, Debug, Hash)]
 pub enum UnsafePolicy {
@@ -52,11 +55,40 @@ impl Parse for UnsafePolicy {
     }
 }
 
+/// How to name overloaded C++ functions and methods in Rust.
+#[derive(PartialEq, Clone, Copy, Debug, Hash)]
+pub enum OverloadNaming {
+    /// `draw`, `draw1`, `draw2`, in the order found in the headers.
+    Numeric,
+    /// Names derived from the parameter types, e.g. `draw_int`, `draw_float`.
+    Descriptive,
+}
+
+impl Parse for OverloadNaming {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let id: syn::Ident = input.parse()?;
+        if id == "numeric" {
+            Ok(OverloadNaming::Numeric)
+        } else if id == "descriptive" {
+            Ok(OverloadNaming::Descriptive)
+        } else {
+            Err(syn::Error::new(
+                id.span(),
+                "expected numeric or descriptive",
+            ))
+        }
+    }
+}
+
 #[derive(Hash)]
 pub enum CppInclusion {
-    #[allow(dead_code)]
-    Define(String), // currently unused, may use in future.
+    /// A preprocessor symbol, with an optional value.
+    Define(String, Option<String>),
+    /// A header given as `#include "path"`.
     Header(String),
+    /// A header given as `#include <path>`, to be found via the
+    /// system include path.
+    SystemHeader(String),
 }
 
 #[derive(Hash)]
@@ -64,8 +96,39 @@ pub struct IncludeCppConfig {
     pub inclusions: Vec<CppInclusion>,
     pub exclude_utilities: bool,
     pub unsafe_policy: UnsafePolicy,
+    pub overload_naming: OverloadNaming,
     pub type_config: TypeConfig,
     pub parse_only: bool,
+    /// The C++ standard from a `cpp_standard!` directive, e.g. `c++17`.
+    pub cpp_standard: Option<String>,
+    /// Arguments from `clang_args!` directives.
+    pub extra_clang_args: Vec<String>,
+    /// Files from which further configuration was read.
+    pub config_files: Vec<PathBuf>,
+    /// The name of the generated mod from a `name!` directive.
+    pub mod_name: Option<syn::Ident>,
+}
+
+impl IncludeCppConfig {
+    /// Reads further configuration from a TOML file, as if it had been
+    /// given by a `config_file!` directive.
+    pub fn merge_config_file(&mut self, path: &Path) -> Result<(), ConfigFileError> {
+        ConfigFile::load(path)?.apply(
+            &mut self.inclusions,
+            &mut self.type_config,
+            &mut self.unsafe_policy,
+        );
+        self.config_files.push(path.to_path_buf());
+        Ok(())
+    }
+
+    /// The name of the mod containing the generated bindings, which
+    /// is `ffi` unless a `name!` directive says otherwise.
+    pub fn get_mod_name(&self) -> syn::Ident {
+        self.mod_name
+            .clone()
+            .unwrap_or_else(|| syn::Ident::new("ffi", proc_macro2::Span::call_site()))
+    }
 }
 
 impl Parse for IncludeCppConfig {
@@ -80,6 +143,11 @@ impl Parse for IncludeCppConfig {
         let mut exclude_utilities = false;
         let mut type_config = TypeConfig::new();
         let mut unsafe_policy = UnsafePolicy::AllFunctionsUnsafe;
+        let mut overload_naming = OverloadNaming::Numeric;
+        let mut cpp_standard = None;
+        let mut extra_clang_args = Vec::new();
+        let mut config_files = Vec::new();
+        let mut mod_name = None;
 
         while !input.is_empty() {
             if input.parse::<Option<syn::Token![#]>>()?.is_some() {
@@ -87,24 +155,124 @@ impl Parse for IncludeCppConfig {
                 if ident != "include" {
                     return Err(syn::Error::new(ident.span(), "expected include"));
                 }
-                let hdr: syn::LitStr = input.parse()?;
-                inclusions.push(CppInclusion::Header(hdr.value()));
+                if input.peek(Token![<]) {
+                    inclusions.push(CppInclusion::SystemHeader(parse_system_header(input)?));
+                } else {
+                    let hdr: syn::LitStr = input.parse()?;
+                    inclusions.push(CppInclusion::Header(hdr.value()));
+                }
             } else {
                 let ident: syn::Ident = input.parse()?;
                 input.parse::<Option<syn::Token![!]>>()?;
                 if ident == "generate" || ident == "generate_pod" {
                     let args;
                     syn::parenthesized!(args in input);
-                    let generate: syn::LitStr = args.parse()?;
-                    type_config.add_to_allowlist(generate.value());
+                    let (generate, span) = parse_pattern(&args)?;
                     if ident == "generate_pod" {
-                        type_config.note_pod_request(generate.value());
+                        type_config.note_pod_request(generate.clone());
+                    }
+                    type_config.add_to_allowlist_with_origin(generate, AllowlistOrigin::Span(span));
+                } else if ident == "generate_all" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    if !args.is_empty() {
+                        return Err(args.error("generate_all! takes no arguments"));
                     }
+                    type_config.set_generate_all();
                 } else if ident == "block" {
                     let args;
                     syn::parenthesized!(args in input);
-                    let generate: syn::LitStr = args.parse()?;
-                    type_config.add_to_blocklist(generate.value());
+                    let (generate, _) = parse_pattern(&args)?;
+                    type_config.add_to_blocklist(generate);
+                } else if ident == "generate_ns" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ns: syn::LitStr = args.parse()?;
+                    let ns_name = ns.value();
+                    if ns_name.is_empty() || TypeConfig::is_pattern(&ns_name) {
+                        return Err(syn::Error::new(
+                            ns.span(),
+                            "expected a C++ namespace such as \"mylib::geometry\"",
+                        ));
+                    }
+                    type_config.add_namespace_to_allowlist(ns_name);
+                } else if ident == "extern_cpp_type" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let cpp_name: syn::LitStr = args.parse()?;
+                    if TypeConfig::is_pattern(&cpp_name.value()) {
+                        return Err(syn::Error::new(
+                            cpp_name.span(),
+                            "expected a C++ type name such as \"mylib::Widget\"",
+                        ));
+                    }
+                    args.parse::<syn::Token![,]>()?;
+                    let rust_path: syn::TypePath = args.parse()?;
+                    type_config.add_extern_cpp_type(cpp_name.value(), rust_path);
+                } else if ident == "define" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let symbol: syn::LitStr = args.parse()?;
+                    let value = if args.parse::<Option<syn::Token![,]>>()?.is_some() {
+                        let value: syn::LitStr = args.parse()?;
+                        Some(value.value())
+                    } else {
+                        None
+                    };
+                    inclusions.push(CppInclusion::Define(symbol.value(), value));
+                } else if ident == "rename" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let cpp_name: syn::LitStr = args.parse()?;
+                    args.parse::<syn::Token![,]>()?;
+                    let rust_name: syn::LitStr = args.parse()?;
+                    if syn::parse_str::<syn::Ident>(&rust_name.value()).is_err() {
+                        return Err(syn::Error::new(
+                            rust_name.span(),
+                            "expected a valid Rust identifier",
+                        ));
+                    }
+                    type_config.add_rename(&cpp_name.value(), rust_name.value());
+                } else if ident == "slice_param" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let cpp_name: syn::LitStr = args.parse()?;
+                    args.parse::<syn::Token![,]>()?;
+                    let pointer_param: syn::LitStr = args.parse()?;
+                    args.parse::<syn::Token![,]>()?;
+                    let len_param: syn::LitStr = args.parse()?;
+                    type_config.add_slice_param(
+                        cpp_name.value(),
+                        pointer_param.value(),
+                        len_param.value(),
+                    );
+                } else if ident == "cpp_standard" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let standard: syn::LitStr = args.parse()?;
+                    cpp_standard = Some(standard.value());
+                } else if ident == "clang_args" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let clang_args =
+                        syn::punctuated::Punctuated::<syn::LitStr, syn::Token![,]>::parse_terminated(
+                            &args,
+                        )?;
+                    extra_clang_args.extend(clang_args.iter().map(syn::LitStr::value));
+                } else if ident == "config_file" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let path: syn::LitStr = args.parse()?;
+                    let resolved_path = ConfigFile::resolve_path(&path.value());
+                    ConfigFile::load(&resolved_path)
+                        .map_err(|e| syn::Error::new(path.span(), e.to_string()))?
+                        .apply(&mut inclusions, &mut type_config, &mut unsafe_policy);
+                    config_files.push(resolved_path);
+                } else if ident == "name" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let name: syn::Ident = args.parse()?;
+                    mod_name = Some(name);
                 } else if ident == "parse_only" {
                     parse_only = true;
                 } else if ident == "exclude_utilities" {
@@ -113,10 +281,14 @@ impl Parse for IncludeCppConfig {
                     let args;
                     syn::parenthesized!(args in input);
                     unsafe_policy = args.parse()?;
+                } else if ident == "overload_naming" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    overload_naming = args.parse()?;
                 } else {
                     return Err(syn::Error::new(
                         ident.span(),
-                        "expected generate, generate_pod, nested_type, safety or exclude_utilities",
+                        "expected generate, generate_pod, generate_ns, generate_all, extern_cpp_type, define, rename, slice_param, config_file, name, cpp_standard, clang_args, nested_type, safety, overload_naming or exclude_utilities",
                     ));
                 }
             }
@@ -134,13 +306,62 @@ impl Parse for IncludeCppConfig {
             type_config,
             parse_only,
             unsafe_policy,
+            overload_naming,
+            cpp_standard,
+            extra_clang_args,
+            config_files,
+            mod_name,
         })
     }
 }
 
+/// Parses the `<path>` of an `#include <path>`. The path isn't a
+/// string literal, so we glue its tokens back together, in just the
+/// same way as cxx does for its own `include!(<path>)`.
+fn parse_system_header(input: ParseStream) -> ParseResult<String> {
+    let langle: Token![<] = input.parse()?;
+    let mut path = String::new();
+    while !input.is_empty() && !input.peek(Token![>]) {
+        let token: proc_macro2::TokenTree = input.parse()?;
+        match token {
+            proc_macro2::TokenTree::Ident(ident) => path += &ident.to_string(),
+            proc_macro2::TokenTree::Punct(punct) => path.push(punct.as_char()),
+            proc_macro2::TokenTree::Literal(lit) => path += &lit.to_string(),
+            _ => {
+                return Err(syn::Error::new(
+                    token.span(),
+                    "unexpected token in include path",
+                ))
+            }
+        }
+    }
+    input.parse::<Token![>]>()?;
+    if path.is_empty() {
+        return Err(syn::Error::new(langle.span, "expected a header path"));
+    }
+    Ok(path)
+}
+
+/// Parses the string literal given to `generate!`, `generate_pod!` or
+/// `block!`. This may be a plain C++ name or a regular expression,
+/// so reject anything which isn't a valid regex here, where we can
+/// still point at the offending literal. Also returns the literal's
+/// span, so that later errors can point at it too.
+fn parse_pattern(input: ParseStream) -> ParseResult<(String, Span)> {
+    let lit: syn::LitStr = input.parse()?;
+    let pattern = lit.value();
+    Regex::new(&pattern).map_err(|e| {
+        syn::Error::new(
+            lit.span(),
+            format!("not a valid name or regular expression: {}", e),
+        )
+    })?;
+    Ok((pattern, lit.span()))
+}
+
 #[cfg(test)]
 mod parse_tests {
-    use crate::config::UnsafePolicy;
+    use crate::config::{CppInclusion, IncludeCppConfig, OverloadNaming, UnsafePolicy};
     use syn::parse_quote;
     #[test]
     fn test_safety_unsafe() {
@@ -163,4 +384,175 @@ mod parse_tests {
         let us: UnsafePolicy = parse_quote! {};
         assert_eq!(us, UnsafePolicy::AllFunctionsUnsafe)
     }
+
+    #[test]
+    fn test_generate_pattern() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            generate!("mylib::net::.*Handler")
+            generate_pod!("mylib::Point.*")
+            block!("mylib::net::Bad.*")
+        };
+        let tc = &cfg.type_config;
+        assert!(tc.is_on_allowlist("mylib::net::FooHandler"));
+        assert!(tc.is_on_allowlist("mylib::Point3D"));
+        assert!(tc.is_pod_requested("mylib::Point3D"));
+        assert!(!tc.is_pod_requested("mylib::net::FooHandler"));
+        assert!(tc.is_on_blocklist("mylib::net::BadHandler"));
+    }
+
+    #[test]
+    fn test_generate_invalid_pattern() {
+        let r: syn::Result<IncludeCppConfig> = syn::parse2(quote::quote! {
+            generate!("mylib::(")
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_generate_ns() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            generate_ns!("mylib::geometry")
+        };
+        let namespaces: Vec<_> = cfg.type_config.allowlisted_namespaces().collect();
+        assert_eq!(namespaces, vec!["mylib::geometry"]);
+    }
+
+    #[test]
+    fn test_generate_all() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            generate_all!()
+        };
+        assert!(cfg.type_config.is_generate_all());
+        let r: syn::Result<IncludeCppConfig> = syn::parse2(quote::quote! {
+            generate_all!("Foo")
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_extern_cpp_type() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            extern_cpp_type!("mylib::Widget", crate::widgets::ffi::mylib::Widget)
+        };
+        assert!(cfg
+            .type_config
+            .get_extern_cpp_type("mylib::Widget")
+            .is_some());
+        let r: syn::Result<IncludeCppConfig> = syn::parse2(quote::quote! {
+            extern_cpp_type!("mylib::.*", crate::widgets::ffi::mylib::Widget)
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_includes() {
+        let cfg: IncludeCppConfig = syn::parse_str(
+            r#"
+            #include "mylib/widget.h"
+            #include <mylib/api-v2.h>
+            "#,
+        )
+        .unwrap();
+        let headers: Vec<_> = cfg
+            .inclusions
+            .iter()
+            .map(|incl| match incl {
+                CppInclusion::Header(path) => (path.as_str(), false),
+                CppInclusion::SystemHeader(path) => (path.as_str(), true),
+                CppInclusion::Define(..) => panic!("unexpected define"),
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            vec![("mylib/widget.h", false), ("mylib/api-v2.h", true)]
+        );
+    }
+
+    #[test]
+    fn test_define() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            define!("MYLIB_ENABLE_FOO", "1")
+            define!("MYLIB_ENABLE_BAR")
+        };
+        let defines: Vec<_> = cfg
+            .inclusions
+            .iter()
+            .map(|incl| match incl {
+                CppInclusion::Define(symbol, value) => (symbol.as_str(), value.as_deref()),
+                CppInclusion::Header(_) | CppInclusion::SystemHeader(_) => {
+                    panic!("unexpected header")
+                }
+            })
+            .collect();
+        assert_eq!(
+            defines,
+            vec![("MYLIB_ENABLE_FOO", Some("1")), ("MYLIB_ENABLE_BAR", None)]
+        );
+    }
+
+    #[test]
+    fn test_cpp_standard_and_clang_args() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            cpp_standard!("c++17")
+            clang_args!("-fno-exceptions", "-Wno-pragma-once-outside-header")
+        };
+        assert_eq!(cfg.cpp_standard.as_deref(), Some("c++17"));
+        assert_eq!(
+            cfg.extra_clang_args,
+            vec!["-fno-exceptions", "-Wno-pragma-once-outside-header"]
+        );
+    }
+
+    #[test]
+    fn test_rename() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            rename!("mylib::Widget::GetSize", "size")
+        };
+        assert_eq!(
+            cfg.type_config.get_rename("mylib::Widget::GetSize"),
+            Some("size")
+        );
+        let r: syn::Result<IncludeCppConfig> = syn::parse2(quote::quote! {
+            rename!("mylib::Widget::GetSize", "not an identifier")
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_slice_param() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            slice_param!("mylib::Process", "data", "len")
+        };
+        let slice_params: Vec<_> = cfg.type_config.get_slice_params("mylib::Process").collect();
+        assert_eq!(slice_params, vec![("data", "len")]);
+        assert_eq!(cfg.type_config.get_slice_params("Process").count(), 0);
+        let r: syn::Result<IncludeCppConfig> = syn::parse2(quote::quote! {
+            slice_param!("mylib::Process", "data")
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_name() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            generate!("draw")
+        };
+        assert_eq!(cfg.get_mod_name(), "ffi");
+        let cfg: IncludeCppConfig = parse_quote! {
+            name!(graphics_ffi)
+        };
+        assert_eq!(cfg.get_mod_name(), "graphics_ffi");
+    }
+
+    #[test]
+    fn test_overload_naming() {
+        let cfg: IncludeCppConfig = parse_quote! {
+            generate!("draw")
+        };
+        assert_eq!(cfg.overload_naming, OverloadNaming::Numeric);
+        let cfg: IncludeCppConfig = parse_quote! {
+            overload_naming!(descriptive)
+        };
+        assert_eq!(cfg.overload_naming, OverloadNaming::Descriptive);
+    }
 }
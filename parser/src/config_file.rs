@@ -0,0 +1,189 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{type_config::TypeConfig, AllowlistOrigin, CppInclusion, UnsafePolicy};
+
+/// Errors which may occur when reading a `config_file!`.
+#[derive(Debug)]
+pub enum ConfigFileError {
+    /// The file couldn't be read.
+    Io(std::io::Error, PathBuf),
+    /// The file wasn't valid TOML, or had unexpected contents.
+    Toml(toml::de::Error, PathBuf),
+    /// An entry in one of the lists wasn't a valid name or regex.
+    InvalidPattern(regex::Error, String),
+    /// The `safety` setting wasn't one we recognize.
+    InvalidSafety(String),
+}
+
+impl Display for ConfigFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigFileError::Io(err, path) => write!(
+                f,
+                "Unable to read config file {}: {}",
+                path.to_string_lossy(),
+                err
+            )?,
+            ConfigFileError::Toml(err, path) => write!(
+                f,
+                "Unable to parse config file {}: {}",
+                path.to_string_lossy(),
+                err
+            )?,
+            ConfigFileError::InvalidPattern(err, item) => write!(
+                f,
+                "{} is not a valid name or regular expression: {}",
+                item, err
+            )?,
+            ConfigFileError::InvalidSafety(safety) => write!(
+                f,
+                "Unknown safety setting {}; expected \"unsafe_ffi\"",
+                safety
+            )?,
+        }
+        Ok(())
+    }
+}
+
+/// The contents of a TOML file referred to by a `config_file!`
+/// directive. Each field corresponds to one of the directives which
+/// could otherwise be given within `include_cpp!`, for example:
+///
+/// ```toml
+/// headers = ["mylib/widget.h", "<mylib/api.h>"]
+/// generate = ["mylib::Widget", "mylib::net::.*Handler"]
+/// generate_pod = ["mylib::Point"]
+/// block = ["mylib::net::BadHandler"]
+/// safety = "unsafe_ffi"
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    #[serde(default)]
+    headers: Vec<String>,
+    #[serde(default)]
+    generate: Vec<String>,
+    #[serde(default)]
+    generate_pod: Vec<String>,
+    #[serde(default)]
+    block: Vec<String>,
+    safety: Option<String>,
+    /// Where this was read from, for reporting bad entries.
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl ConfigFile {
+    pub(crate) fn load(path: &Path) -> Result<Self, ConfigFileError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigFileError::Io(e, path.to_path_buf()))?;
+        let mut config: ConfigFile =
+            toml::from_str(&contents).map_err(|e| ConfigFileError::Toml(e, path.to_path_buf()))?;
+        config.path = path.to_path_buf();
+        for item in config
+            .generate
+            .iter()
+            .chain(config.generate_pod.iter())
+            .chain(config.block.iter())
+        {
+            Regex::new(item).map_err(|e| ConfigFileError::InvalidPattern(e, item.clone()))?;
+        }
+        if let Some(safety) = &config.safety {
+            if safety != "unsafe_ffi" && safety != "unsafe" {
+                return Err(ConfigFileError::InvalidSafety(safety.clone()));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Relative paths are relative to the crate being built, since
+    /// that's where both the macro and build scripts run.
+    pub(crate) fn resolve_path(path: &str) -> PathBuf {
+        let path = PathBuf::from(path);
+        match std::env::var_os("CARGO_MANIFEST_DIR") {
+            Some(manifest_dir) if path.is_relative() => PathBuf::from(manifest_dir).join(path),
+            _ => path,
+        }
+    }
+
+    pub(crate) fn apply(
+        self,
+        inclusions: &mut Vec<CppInclusion>,
+        type_config: &mut TypeConfig,
+        unsafe_policy: &mut UnsafePolicy,
+    ) {
+        inclusions.extend(self.headers.into_iter().map(|header| {
+            // As in C++, a header in angle brackets is a system header.
+            match header.strip_prefix('<').and_then(|h| h.strip_suffix('>')) {
+                Some(path) => CppInclusion::SystemHeader(path.to_string()),
+                None => CppInclusion::Header(header),
+            }
+        }));
+        let path = self.path;
+        let origin = |key| AllowlistOrigin::ConfigFile(path.clone(), key);
+        for item in self.generate {
+            type_config.add_to_allowlist_with_origin(item, origin("generate"));
+        }
+        for item in self.generate_pod {
+            type_config.note_pod_request(item.clone());
+            type_config.add_to_allowlist_with_origin(item, origin("generate_pod"));
+        }
+        for item in self.block {
+            type_config.add_to_blocklist(item);
+        }
+        if self.safety.is_some() {
+            *unsafe_policy = UnsafePolicy::AllFunctionsSafe;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFile, ConfigFileError};
+
+    #[test]
+    fn test_parse_config_file() {
+        let config: ConfigFile = toml::from_str(
+            r#"
+            headers = ["a.h"]
+            generate = ["A::.*"]
+            safety = "unsafe_ffi"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.headers, vec!["a.h"]);
+        assert_eq!(config.generate, vec!["A::.*"]);
+        assert!(config.generate_pod.is_empty());
+        assert_eq!(config.safety.as_deref(), Some("unsafe_ffi"));
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let r: Result<ConfigFile, _> = toml::from_str("generate_everything = true");
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let r = ConfigFile::load(&ConfigFile::resolve_path("/nonexistent/bindings.toml"));
+        assert!(matches!(r, Err(ConfigFileError::Io(..))));
+    }
+}
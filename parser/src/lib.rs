@@ -13,6 +13,7 @@
 // limitations under the License.
 
 mod config;
+mod config_file;
 pub mod file_locations;
 mod type_config;
 
@@ -21,7 +22,8 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-pub use config::{CppInclusion, IncludeCppConfig, UnsafePolicy};
+pub use config::{CppInclusion, IncludeCppConfig, OverloadNaming, UnsafePolicy};
+pub use config_file::ConfigFileError;
 use file_locations::FileLocationStrategy;
 use proc_macro2::TokenStream as TokenStream2;
 use syn::Result as ParseResult;
@@ -30,7 +32,7 @@ use syn::{
     Macro,
 };
 
-pub use type_config::TypeConfig;
+pub use type_config::{AllowlistOrigin, TypeConfig};
 
 /// Core of the autocxx engine. See `generate` for most details
 /// on how this works.
@@ -80,4 +82,15 @@ mod parse_tests {
     fn test_basic() {
         let _i: IncludeCpp = parse_quote! {};
     }
+
+    #[test]
+    fn test_name_affects_rs_filename() {
+        let a: IncludeCpp = parse_quote! {
+            name!(graphics_ffi)
+        };
+        let b: IncludeCpp = parse_quote! {
+            name!(audio_ffi)
+        };
+        assert_ne!(a.get_rs_filename(), b.get_rs_filename());
+    }
 }
@@ -12,14 +12,90 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use once_cell::unsync::OnceCell;
+use proc_macro2::Span;
+use quote::ToTokens;
+use regex::RegexSet;
+use syn::TypePath;
+
+/// Where an allowlist entry was given, so that we can say where to fix
+/// it if it matches nothing.
+#[derive(Clone, Debug)]
+pub enum AllowlistOrigin {
+    /// Written within `include_cpp!`.
+    Span(Span),
+    /// Listed under the given key (e.g. `generate`) of a config file.
+    ConfigFile(PathBuf, &'static str),
+}
+
+/// A list of items from `generate!`, `block!` or similar directives.
+/// Each is treated as a regular expression which must match the whole
+/// C++ name, exactly as bindgen does for its own allowlist and blocklist,
+/// so that bindgen and our own later passes agree on what's included.
+#[derive(Default)]
+struct PatternList {
+    items: Vec<String>,
+    /// Where each item was given, if we know.
+    origins: Vec<Option<AllowlistOrigin>>,
+    regexes: OnceCell<RegexSet>,
+}
+
+impl PatternList {
+    fn push(&mut self, item: String, origin: Option<AllowlistOrigin>) {
+        self.items.push(item);
+        self.origins.push(origin);
+        self.regexes = OnceCell::new();
+    }
+
+    fn get_regexes(&self) -> &RegexSet {
+        self.regexes.get_or_init(|| {
+            RegexSet::new(self.items.iter().map(|item| format!("^{}$", item)))
+                .expect("Patterns should have been validated during parsing")
+        })
+    }
+
+    fn is_match(&self, cpp_name: &str) -> bool {
+        self.get_regexes().is_match(cpp_name)
+    }
+}
+
+impl Hash for PatternList {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.items.hash(state)
+    }
+}
+
+/// Types from `extern_cpp_type!` directives, keyed by C++ name, along
+/// with the Rust path of the binding which already exists for each.
+#[derive(Default)]
+struct ExternCppTypes(BTreeMap<String, TypePath>);
+
+impl Hash for ExternCppTypes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for (cpp_name, rust_path) in &self.0 {
+            cpp_name.hash(state);
+            rust_path.to_token_stream().to_string().hash(state);
+        }
+    }
+}
+
 /// Central registry of all information known about types.
 /// At present this is very minimal; in future we should roll
 /// known_types.rs into this and possibly other things as well.
 #[derive(Default, Hash)]
 pub struct TypeConfig {
-    pod_requests: Vec<String>,
-    allowlist: Vec<String>, // not TypeName as it may be funcs not types.
-    blocklist: Vec<String>, // not TypeName as it may be funcs not types.
+    pod_requests: PatternList,
+    allowlist: PatternList, // not TypeName as it may be funcs not types.
+    blocklist: PatternList, // not TypeName as it may be funcs not types.
+    allowlisted_namespaces: Vec<String>,
+    renames: BTreeMap<String, String>,
+    slice_params: BTreeMap<String, Vec<(String, String)>>,
+    extern_cpp_types: ExternCppTypes,
+    generate_all: bool,
 }
 
 impl TypeConfig {
@@ -28,27 +104,119 @@ impl TypeConfig {
     }
 
     pub(crate) fn note_pod_request(&mut self, tn: String) {
-        self.pod_requests.push(tn);
+        self.pod_requests.push(tn, None);
     }
 
     pub(crate) fn add_to_allowlist(&mut self, item: String) {
-        self.allowlist.push(item);
+        self.allowlist.push(item, None);
+    }
+
+    /// Adds an item to the allowlist, remembering where it was given
+    /// so that we can complain usefully if it can't be found.
+    pub(crate) fn add_to_allowlist_with_origin(&mut self, item: String, origin: AllowlistOrigin) {
+        self.allowlist.push(item, Some(origin));
+    }
+
+    pub(crate) fn set_generate_all(&mut self) {
+        self.generate_all = true;
+    }
+
+    /// Whether `generate_all!` was given, in which case everything
+    /// bindgen finds in the headers is considered allowlisted.
+    pub fn is_generate_all(&self) -> bool {
+        self.generate_all
+    }
+
+    pub(crate) fn add_namespace_to_allowlist(&mut self, ns: String) {
+        self.allowlisted_namespaces.push(ns);
+    }
+
+    pub(crate) fn add_rename(&mut self, cpp_name: &str, rust_name: String) {
+        self.renames
+            .insert(Self::normalize_rename_key(cpp_name), rust_name);
+    }
+
+    /// Whitespace within parameter lists is insignificant, so strip it
+    /// before using a name as a key for `rename!` lookups.
+    fn normalize_rename_key(cpp_name: &str) -> String {
+        cpp_name.chars().filter(|c| !c.is_whitespace()).collect()
+    }
+
+    /// Any Rust name chosen by the user via `rename!` for this C++
+    /// function or method. The name may optionally include a parameter
+    /// list, e.g. `ns::Type::method(uint32_t, const std::string&)`.
+    pub fn get_rename(&self, cpp_name: &str) -> Option<&str> {
+        self.renames
+            .get(&Self::normalize_rename_key(cpp_name))
+            .map(String::as_str)
+    }
+
+    pub(crate) fn add_slice_param(
+        &mut self,
+        cpp_name: String,
+        pointer_param: String,
+        len_param: String,
+    ) {
+        self.slice_params
+            .entry(cpp_name)
+            .or_default()
+            .push((pointer_param, len_param));
+    }
+
+    /// The pointer and length parameters of this C++ function or method
+    /// which `slice_param!` directives ask us to pass as a single slice.
+    pub fn get_slice_params(&self, cpp_name: &str) -> impl Iterator<Item = (&str, &str)> {
+        self.slice_params
+            .get(cpp_name)
+            .into_iter()
+            .flatten()
+            .map(|(pointer_param, len_param)| (pointer_param.as_str(), len_param.as_str()))
+    }
+
+    pub(crate) fn add_extern_cpp_type(&mut self, cpp_name: String, rust_path: TypePath) {
+        self.extern_cpp_types.0.insert(cpp_name, rust_path);
+    }
+
+    /// If this type was declared with `extern_cpp_type!`, the Rust path
+    /// of the existing binding which should be used instead of
+    /// generating a new one.
+    pub fn get_extern_cpp_type(&self, cpp_name: &str) -> Option<&TypePath> {
+        self.extern_cpp_types.0.get(cpp_name)
     }
 
     pub(crate) fn add_to_blocklist(&mut self, item: String) {
-        self.blocklist.push(item);
+        self.blocklist.push(item, None);
+    }
+
+    /// Whether a `generate!` or similar entry is a pattern rather than
+    /// a plain C++ name.
+    pub fn is_pattern(item: &str) -> bool {
+        regex::escape(item) != item
     }
 
     pub fn get_pod_requests(&self) -> &[String] {
-        &self.pod_requests
+        &self.pod_requests.items
+    }
+
+    /// Whether this type matches any of the `generate_pod!` entries.
+    pub fn is_pod_requested(&self, cpp_name: &str) -> bool {
+        self.pod_requests.is_match(cpp_name)
     }
 
     pub fn allowlist(&self) -> impl Iterator<Item = &String> {
-        self.allowlist.iter()
+        self.allowlist.items.iter()
+    }
+
+    /// Namespaces from `generate_ns!` directives. Everything within
+    /// these (including nested namespaces) is considered allowlisted.
+    pub fn allowlisted_namespaces(&self) -> impl Iterator<Item = &String> {
+        self.allowlisted_namespaces.iter()
     }
 
     pub fn allowlist_is_empty(&self) -> bool {
-        self.allowlist.is_empty()
+        !self.generate_all
+            && self.allowlist.items.is_empty()
+            && self.allowlisted_namespaces.is_empty()
     }
 
     /// Whether this type is on the allowlist specified by the user.
@@ -59,15 +227,136 @@ impl TypeConfig {
     ///    we pass to cxx.
     /// This second pass may seem redundant. But sometimes bindgen generates
     /// unnecessary stuff.
+    ///
+    /// Everything is on the allowlist if `generate_all!` was given.
+    /// This does not consider `generate_ns!` namespaces; see
+    /// [TypeConfig::allowlisted_namespaces].
     pub fn is_on_allowlist(&self, cpp_name: &str) -> bool {
-        self.allowlist.contains(&cpp_name.to_string())
+        self.generate_all || self.allowlist.is_match(cpp_name)
+    }
+
+    /// Those allowlist entries given by the user, either within
+    /// `include_cpp!` or in a config file, which match none of the given
+    /// C++ names, along with where they were given.
+    pub fn unmatched_allowlist_entries<'a>(
+        &self,
+        cpp_names: impl Iterator<Item = &'a str>,
+    ) -> Vec<(&str, &AllowlistOrigin)> {
+        let mut matched = vec![false; self.allowlist.items.len()];
+        let regexes = self.allowlist.get_regexes();
+        for cpp_name in cpp_names {
+            for idx in regexes.matches(cpp_name).iter() {
+                matched[idx] = true;
+            }
+        }
+        self.allowlist
+            .items
+            .iter()
+            .zip(self.allowlist.origins.iter())
+            .zip(matched)
+            .filter_map(|((item, origin), matched)| match origin {
+                Some(origin) if !matched => Some((item.as_str(), origin)),
+                _ => None,
+            })
+            .collect()
     }
 
     pub fn is_on_blocklist(&self, cpp_name: &str) -> bool {
-        self.blocklist.contains(&cpp_name.to_string())
+        self.blocklist.is_match(cpp_name)
     }
 
     pub fn get_blocklist(&self) -> impl Iterator<Item = &String> {
-        self.blocklist.iter()
+        self.blocklist.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AllowlistOrigin, TypeConfig};
+    use proc_macro2::Span;
+    use quote::ToTokens;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_exact_names() {
+        let mut tc = TypeConfig::new();
+        tc.add_to_allowlist("A::Bob".into());
+        assert!(tc.is_on_allowlist("A::Bob"));
+        assert!(!tc.is_on_allowlist("A::Bobby"));
+        assert!(!tc.is_on_allowlist("Bob"));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut tc = TypeConfig::new();
+        tc.add_to_allowlist("mylib::net::.*Handler".into());
+        tc.add_to_blocklist("mylib::net::Bad.*".into());
+        assert!(tc.is_on_allowlist("mylib::net::FooHandler"));
+        assert!(tc.is_on_allowlist("mylib::net::BadHandler"));
+        assert!(!tc.is_on_allowlist("mylib::net::FooHandlerImpl"));
+        assert!(!tc.is_on_allowlist("mylib::FooHandler"));
+        assert!(tc.is_on_blocklist("mylib::net::BadHandler"));
+        assert!(!tc.is_on_blocklist("mylib::net::FooHandler"));
+    }
+
+    #[test]
+    fn test_unmatched_allowlist_entries() {
+        let mut tc = TypeConfig::new();
+        let span = AllowlistOrigin::Span(Span::call_site());
+        let config_file = AllowlistOrigin::ConfigFile("bindings.toml".into(), "generate");
+        tc.add_to_allowlist_with_origin("A::Bob".into(), span.clone());
+        tc.add_to_allowlist_with_origin("A::Fooo".into(), span.clone());
+        tc.add_to_allowlist_with_origin("B::.*".into(), span);
+        tc.add_to_allowlist_with_origin("C::Bobb".into(), config_file);
+        tc.add_to_allowlist("make_string".into());
+        let unmatched: Vec<_> = tc
+            .unmatched_allowlist_entries(["A::Bob", "A::Foo", "C::Bob"].iter().cloned())
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect();
+        assert_eq!(unmatched, vec!["A::Fooo", "B::.*", "C::Bobb"]);
+    }
+
+    #[test]
+    fn test_is_pattern() {
+        assert!(!TypeConfig::is_pattern("A::B::Bob"));
+        assert!(TypeConfig::is_pattern("A::B::.*"));
+    }
+
+    #[test]
+    fn test_generate_all() {
+        let mut tc = TypeConfig::new();
+        assert!(tc.allowlist_is_empty());
+        tc.set_generate_all();
+        assert!(!tc.allowlist_is_empty());
+        assert!(tc.is_on_allowlist("A::Bob"));
+    }
+
+    #[test]
+    fn test_renames() {
+        let mut tc = TypeConfig::new();
+        tc.add_rename("A::Bob::GetSize", "size".into());
+        tc.add_rename(
+            "A::foo(uint32_t, const std::string&)",
+            "foo_with_str".into(),
+        );
+        assert_eq!(tc.get_rename("A::Bob::GetSize"), Some("size"));
+        assert_eq!(
+            tc.get_rename("A::foo(uint32_t,const std::string &)"),
+            Some("foo_with_str")
+        );
+        assert_eq!(tc.get_rename("A::foo"), None);
+    }
+
+    #[test]
+    fn test_extern_cpp_types() {
+        let mut tc = TypeConfig::new();
+        tc.add_extern_cpp_type("A::Bob".into(), parse_quote! { crate::a::ffi::A::Bob });
+        let rust_path = tc.get_extern_cpp_type("A::Bob").unwrap();
+        assert_eq!(
+            rust_path.to_token_stream().to_string(),
+            "crate :: a :: ffi :: A :: Bob"
+        );
+        assert!(tc.get_extern_cpp_type("A::Bobby").is_none());
     }
 }
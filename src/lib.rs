@@ -96,9 +96,12 @@ use autocxx_engine::IncludeCppEngine;
 /// Within the brackets of the `include_cxx!(...)` macro, you should provide
 /// a list of at least the following:
 ///
-/// * `#include "cpp_header.h"`: a header filename to parse and include
+/// * `#include "cpp_header.h"`: a header filename to parse and include.
+///   `#include <mylib/api.h>` also works, for headers which should be
+///   found via the system include path.
 /// * `generate!("type_or_function_name")`: a type or function name whose declaration
-///   should be made available to C++.
+///   should be made available to C++. Alternatively, [generate_all] makes
+///   everything available.
 /// * Possibly, `safety!(unsafe)` - see discussion of `unsafe` later.
 ///
 /// Other directives are possible as documented in this crate.
@@ -136,6 +139,9 @@ use autocxx_engine::IncludeCppEngine;
 /// }
 /// ```
 ///
+/// If you have more than one `include_cpp!` in the same mod, give each
+/// a different name using [name], e.g. `name!(graphics_ffi)`.
+///
 /// # Built-in types
 ///
 /// The generated code uses `cxx` for interop: see that crate for many important
@@ -166,8 +172,8 @@ use autocxx_engine::IncludeCppEngine;
 /// ## Preprocessor symbols
 ///
 /// `#define` and other preprocessor symbols will appear as constants.
-/// At present there is no way to do compile-time disablement of code
-/// (equivalent of `#ifdef`).
+/// To parse and build your headers with particular symbols defined,
+/// use [define]; these are set up before any header is included.
 ///
 /// ## Integer types
 ///
@@ -175,7 +181,25 @@ use autocxx_engine::IncludeCppEngine;
 /// For types such as `int` or `unsigned long`, the hope is that you can
 /// eventually use `std::os::raw::c_int` oor `std::os::raw::c_ulong` etc.
 /// For now, this doesn't quite work: instead you need to wrap these values
-/// in a newtype wrapper such as [c_int] or [c_ulong] in this crate.
+/// in a newtype wrapper such as [c_int] or [c_ulong] in this crate. There's
+/// one for each of `short`, `int`, `long` and `long long` (signed and
+/// unsigned) and for `signed char` and `unsigned char`. Plain `char` is
+/// simply `std::os::raw::c_char`.
+///
+/// `size_t` and `uintptr_t` become `usize`, and `ssize_t`, `intptr_t` and
+/// `ptrdiff_t` become `isize`, on all platforms.
+///
+/// `wchar_t`, `char16_t` and `char32_t` are unfortunately indistinguishable
+/// from fixed-size integers by the time `bindgen` tells us about them, so
+/// functions using them won't yet work.
+///
+/// cxx can't put any primitive type inside a `UniquePtr`, nor the C type
+/// wrappers inside any container, so within those we instead use a
+/// transparent newtype, such as `AutocxxPrimitiveU32` for `u32` or
+/// `AutocxxPrimitiveCInt` for [c_int]. A `std::unique_ptr<int>` is therefore
+/// a `UniquePtr<ffi::AutocxxPrimitiveCInt>`. These newtypes `Deref` to
+/// the primitive, can be compared with it and can be created from it
+/// using `From`.
 ///
 /// ## String constants
 ///
@@ -203,6 +227,19 @@ use autocxx_engine::IncludeCppEngine;
 ///
 /// C++ allows function overloads; Rust doesn't. `autocxx` follows the lead
 /// of `bindgen` here and generating overloads as `func`, `func1`, `func2` etc.
+/// If you'd rather choose the names yourself, use [rename]. Alternatively,
+/// `overload_naming!(descriptive)` names every overload after its parameter
+/// types, e.g. `draw_int` and `draw_float`, so the names stay the same even
+/// if the declarations are reordered. See [overload_naming].
+///
+/// ## Sharing types between `include_cpp!` blocks
+///
+/// Each `include_cpp!` normally generates its own Rust type for every C++
+/// type it encounters, and those types are incompatible with one another.
+/// If a type has already been generated elsewhere (by another `include_cpp!`
+/// or in another crate) use [extern_cpp_type] to refer to those existing
+/// bindings instead. Such types are treated as non-POD unless they're also
+/// given to [generate_pod], in which case the existing bindings must be POD too.
 ///
 /// ## C++ classes - why do I get warnings?
 ///
@@ -219,17 +256,114 @@ use autocxx_engine::IncludeCppEngine;
 /// Naturally, such an object can't be passed by value either; it can still be
 /// referenced in Rust references.
 ///
+/// ## Arrays
+///
+/// Fixed-size array fields, such as `int data[16]`, appear as Rust arrays
+/// (`[c_int; 16]`) and don't prevent a struct being POD, so long as the
+/// elements could themselves be POD. Array parameters, such as
+/// `const uint32_t a[4]`, and references to arrays, become `&[u32; 4]`
+/// (or `&mut [u32; 4]` if not `const`).
+///
+/// ## Bitfields
+///
+/// Structs containing bitfields, such as `uint32_t kind : 3`, may still be
+/// given to [generate_pod]. The bits are stored in opaque `_bitfield_`
+/// fields which match the C++ layout, and each bitfield is accessed through
+/// getter and setter methods named after it: `kind()` and `set_kind(value)`. Non-POD
+/// types have no such methods, since their fields aren't visible at all.
+///
+/// ## Unions
+///
+/// A C++ union whose members could all be POD becomes a Rust `union` if
+/// it's given to [generate_pod], or is part of a struct which is. Reading
+/// a member is `unsafe`, as with any Rust union. Members which might not be
+/// `Copy` (anything except primitives, pointers and arrays of those) are
+/// wrapped in [`std::mem::ManuallyDrop`]. There's also a
+/// `bindgen_union_field` member holding the raw storage. Other unions
+/// are opaque, non-POD types, just like classes.
+///
+/// ## Slices
+///
+/// Many C-style functions take a pointer and a length, such as
+/// `void Process(const uint8_t* data, size_t len)`. Use [slice_param] to
+/// pass these as a single `&[u8]` (or `&mut [u8]` if the pointer isn't
+/// `const`). Parameters of type `std::span<T>` become `&mut [T]`, and
+/// `std::span` needs `cpp_standard!("c++20")` or later. In both cases, the
+/// elements must be things which can be passed by value, such as
+/// primitives or types given to [generate_pod].
+///
+/// A known limitation is that a `std::span<const T>` also becomes a
+/// `&mut [T]`, rather than a `&[T]`, because bindgen doesn't tell us
+/// whether the elements of a span are `const`. So callers need mutable
+/// access to their data even to pass it to a function which only reads
+/// it.
+///
+/// ## String views
+///
+/// `std::string_view` parameters appear as `&str` in Rust. Return values
+/// appear as `&[u8]`, since C++ doesn't promise that they contain UTF-8:
+/// use [std::str::from_utf8] if you need a `&str`. Returning a reference
+/// requires a lifetime, so as with other references, functions returning
+/// a `std::string_view` are only supported if they take exactly one
+/// reference parameter (for example, a `const` method).
+///
+/// ## Optional values
+///
+/// Functions taking or returning `std::optional<T>` take or return an
+/// `Option` in Rust. If `T` can be passed by value in Rust (for instance,
+/// a primitive or a type given to [generate_pod]), that's `Option<T>`;
+/// otherwise it's `Option<UniquePtr<T>>`. As `std::optional` is a
+/// C++17 feature, you'll need `cpp_standard!("c++17")` or later.
+///
+/// ## Callbacks
+///
+/// Parameters of type `std::function` (or a `const` reference to one) take
+/// a Rust closure, e.g. `impl Fn(u32) + Send + Sync + 'static` for a
+/// `std::function<void(uint32_t)>`. The closure is boxed up and owned by the
+/// `std::function` (and any copies of it) and is dropped when the last such
+/// copy is destroyed. C++ may call it at any time until then, from any
+/// thread, and even from within a call to itself, which is why it must be
+/// `Fn`, `Send` and `Sync`: to keep state between calls, use something
+/// like an atomic or a `Mutex`. Closures may take and return references
+/// and anything which can be passed by value.
+///
 /// ## Generic types
 ///
 /// If you're using one of the generic types which is supported natively by cxx,
 /// e.g. `std::unique_ptr`, it should work as you expect. For other generic types,
 /// we synthesize a concrete Rust type, corresponding to a C++ typedef, for each
 /// concrete instantiation of the type. Such generated types are always opaque,
-/// and never have methods attached. That's therefore enough to pass them
-/// between return types and parameters of other functions within `UniquePtr`s
-/// but not really enough to do anything else with these types just yet. Hopefully,
-/// this will be improved in future. At present such types have a name
-/// `AutocxxConcrete{n}` but this may change in future.
+/// and (with the exception of maps, pairs and tuples, below) never have methods attached. That's
+/// therefore enough to pass them between return types and parameters of other
+/// functions within `UniquePtr`s but not really enough to do anything else with
+/// these types just yet. Hopefully, this will be improved in future. At present
+/// such types have a name `AutocxxConcrete{n}` but this may change in future.
+///
+/// ## Maps
+///
+/// Concrete `std::map<K, V>` and `std::unordered_map<K, V>` types are given
+/// methods `len`, `is_empty`, `contains`, `get` (returning an `Option<&V>`),
+/// `insert`, `keys` and `iter`. `iter` walks the map's own C++ iterator,
+/// yielding references to each key and value in the map's order. `insert`
+/// takes primitive keys and values by reference and copies them, but
+/// anything else must be given as a `UniquePtr`, whose contents are moved
+/// into the map, so types which can't be copied in C++ work too.
+///
+/// ## Shared and weak pointers
+///
+/// `std::shared_ptr<T>` and `std::weak_ptr<T>` are cxx's `SharedPtr<T>` and
+/// `WeakPtr<T>`. For any type we generate, `SharedPtr::downgrade` makes a
+/// `WeakPtr`, and `WeakPtr::upgrade` does what `lock` does in C++, returning
+/// a null `SharedPtr` if the object has gone.
+///
+/// ## Pairs and tuples
+///
+/// A `std::pair` or `std::tuple` of types which can be passed by value,
+/// such as `std::pair<int, float>` or `std::tuple<int, float, bool>`, is
+/// a Rust tuple when passed to or returned from a function. Other pairs
+/// are opaque types with `first` and `second` methods, and other tuples
+/// are opaque types with a `get::<N>()` method for each member, as with
+/// `std::get<N>` in C++. A `std::tuple` may have at most 12 members.
 ///
 /// # Internals
 ///
@@ -258,6 +392,12 @@ macro_rules! include {
 }
 
 /// Generate Rust bindings for the given C++ type or function.
+/// The name may also be a regular expression, such as
+/// `"mylib::net::.*Handler"`, which is matched against the whole
+/// C++ name, including namespaces.
+/// It's an error if a name or pattern doesn't match anything in
+/// the headers; the error will point at the entry and suggest
+/// similar names which do exist.
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 /// See also [generate_pod].
@@ -273,6 +413,8 @@ macro_rules! generate {
 /// destructor - you'll encounter a compile error otherwise.
 /// If your type doesn't match that description, use [generate]
 /// instead, and own the type using [UniquePtr][autocxx_engine::cxx::UniquePtr].
+/// As with [generate], a regular expression may be given instead of
+/// a single name.
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
@@ -280,6 +422,144 @@ macro_rules! generate_pod {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Generate Rust bindings for everything found in the headers, rather
+/// than just the items you list, i.e. `generate_all!()`. This is most
+/// useful for small C-style headers. The standard library is still
+/// excluded, and any items which autocxx can't yet convert are skipped
+/// instead of causing an error.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_all {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate Rust bindings for everything within the given C++
+/// namespace, e.g. `generate_ns!("mylib::geometry")`. This includes
+/// any nested namespaces. Individual items can still be excluded
+/// using [block].
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_ns {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Use bindings for a C++ type which have already been generated
+/// elsewhere, rather than generating new ones, e.g.
+/// `extern_cpp_type!("mylib::Widget", crate::widgets::ffi::mylib::Widget)`.
+/// The Rust path should be absolute, and the type it refers to must
+/// implement `cxx::ExternType` for the same C++ name, as the output of
+/// any other `include_cpp!` does.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! extern_cpp_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Choose the name of the mod containing the generated bindings,
+/// e.g. `name!(graphics_ffi)`, instead of the default `ffi`. The
+/// generated C++ headers are named after it too, e.g.
+/// `graphics_ffi_cxxgen.h`.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! name {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Define a preprocessor symbol, optionally with a value, e.g.
+/// `define!("MYLIB_ENABLE_FOO", "1")`. The symbol is defined before any
+/// of the headers are included, both when bindgen parses them and
+/// when the generated C++ is compiled.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! define {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Choose the Rust name for a C++ function or method, instead of the
+/// one autocxx would otherwise pick, e.g.
+/// `rename!("mylib::Widget::GetSize", "size")`. To rename just one
+/// of several overloads, give its parameter types as well:
+/// `rename!("mylib::Widget::Resize(uint32_t, uint32_t)", "resize_xy")`.
+/// Constructors are named after their type, like `"mylib::Widget::Widget"`.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! rename {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Pass a C-style pointer and length to a C++ function or method as a
+/// single Rust slice, e.g. `slice_param!("mylib::Process", "data", "len")`
+/// for `void Process(const uint8_t* data, size_t len)`, which then takes a
+/// `&[u8]`. The length parameter must be an integer immediately following
+/// the pointer, and a non-`const` pointer results in a `&mut [T]`. Passing
+/// a slice too long for the length parameter's type panics.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! slice_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Read further directives from a TOML file, e.g.
+/// `config_file!("bindings.toml")`. This is useful for long allowlists,
+/// perhaps shared between crates or generated by scripts.
+/// Relative paths are relative to the directory containing your
+/// `Cargo.toml`. The file may contain any of these keys:
+///
+/// ```toml
+/// headers = ["mylib/widget.h"]          # like #include "mylib/widget.h"
+/// generate = ["mylib::Widget"]          # like generate!
+/// generate_pod = ["mylib::Point"]       # like generate_pod!
+/// block = ["mylib::net::BadHandler"]    # like block!
+/// safety = "unsafe_ffi"                 # like safety!(unsafe_ffi)
+/// ```
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! config_file {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Choose how overloaded functions and methods are named in Rust.
+/// `overload_naming!(numeric)`, the default, names them `draw`, `draw1`
+/// and so on, in the order they're declared. `overload_naming!(descriptive)`
+/// instead names each overload after its parameter types, e.g. `draw_int`
+/// and `draw_float`. Functions which aren't overloaded keep their names.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! overload_naming {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Choose the C++ standard used to parse the headers and to build
+/// the generated C++, e.g. `cpp_standard!("c++17")`. The default is
+/// `c++14`.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! cpp_standard {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Pass extra arguments to clang when parsing the headers, e.g.
+/// `clang_args!("-fno-exceptions")`. These are also passed to the
+/// C++ compiler when building the generated C++, so use only
+/// arguments which your compiler understands too.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! clang_args {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Skip the normal generation of a `make_string` function
 /// and other utilities which we might generate normally.
 /// A directive to be included inside
@@ -295,6 +575,8 @@ macro_rules! exclude_utilities {
 /// otherwise generated.
 /// This is 'greedy' in the sense that any functions/methods
 /// which take or return such a type will _also_ be blocked.
+/// As with [generate], a regular expression may be given instead of
+/// a single name.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
@@ -356,6 +638,15 @@ macro_rules! usage {
 #[doc(hidden)]
 pub use autocxx_macro::include_cpp_impl;
 
+/// Implemented for each member of any `std::tuple` which is an opaque
+/// type, so that it has a `get::<N>()` method returning that member.
+pub trait TupleElement<const N: usize> {
+    /// The type of member `N`.
+    type Element;
+    /// Returns member `N`. Call the tuple's `get::<N>()` method instead.
+    fn get_element(&self) -> &Self::Element;
+}
+
 macro_rules! ctype_wrapper {
     ($r:ident, $c:expr, $d:expr) => {
         #[doc=$d]
@@ -381,4 +672,11 @@ ctype_wrapper!(
 ctype_wrapper!(c_short, "c_short", "Newtype wrapper for an short");
 ctype_wrapper!(c_uint, "c_uint", "Newtype wrapper for an unsigned int");
 ctype_wrapper!(c_int, "c_int", "Newtype wrapper for an int");
+ctype_wrapper!(
+    c_ulonglong,
+    "c_ulonglong",
+    "Newtype wrapper for an unsigned long long"
+);
+ctype_wrapper!(c_longlong, "c_longlong", "Newtype wrapper for a long long");
 ctype_wrapper!(c_uchar, "c_uchar", "Newtype wrapper for an unsigned char");
+ctype_wrapper!(c_schar, "c_schar", "Newtype wrapper for a signed char");